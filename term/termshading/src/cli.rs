@@ -0,0 +1,94 @@
+use std::error::Error;
+
+use crate::math::Vec3;
+use crate::{Float, Int};
+
+
+
+pub enum Mode {
+    Interactive,
+    Render,
+    Bench(usize),
+}
+
+/// everything that can be set from the command line. `termshading` alone runs the interactive
+/// viewer, `termshading render --out <file>` draws a single frame without needing a terminal and
+/// `termshading --bench <frames>` times the render passes headless. rendered images leave out
+/// the panel and labels unless given `--overlay`
+pub struct Args {
+    pub mode: Mode,
+    pub out: Option<String>,
+    pub pos: Option<Vec3>,
+    pub target: Option<String>,
+    pub fov: Option<Float>,
+    pub size: Option<(Int, Int)>,
+    pub supersample: Option<(Int, Int)>,
+    pub date: Option<String>,
+    pub scale: Int,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub looping: bool,
+    pub overlay: bool,
+}
+
+impl Args {
+    pub fn parse(args: &[String]) -> Result<Args, Box<dyn Error>> {
+        let mut parsed = Args {
+            mode: Mode::Interactive, out: None, pos: None, target: None, fov: None, size: None,
+            supersample: None,
+            date: None, scale: 4, record: None,
+            replay: None, looping: false, overlay: false,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "render" {
+                parsed.mode = Mode::Render;
+                continue;
+            }
+            if arg == "--loop" {
+                parsed.looping = true;
+                continue;
+            }
+            if arg == "--overlay" {
+                parsed.overlay = true;
+                continue;
+            }
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--out" => parsed.out = Some(value()?.to_owned()),
+                "--pos" => parsed.pos = Some(parse_vec(value()?)?),
+                "--target" => parsed.target = Some(value()?.to_owned()),
+                "--fov" => parsed.fov = Some(value()?.parse()?),
+                "--size" => parsed.size = Some(parse_size(value()?)?),
+                "--supersample" => parsed.supersample = Some(parse_size(value()?)?),
+                "--date" => parsed.date = Some(value()?.to_owned()),
+                "--scale" => parsed.scale = value()?.parse()?,
+                "--record" => parsed.record = Some(value()?.to_owned()),
+                "--replay" => parsed.replay = Some(value()?.to_owned()),
+                "--bench" => parsed.mode = Mode::Bench(value()?.parse()?),
+                _ => return Err(format!("unknown argument {}", arg).into()),
+            }
+        }
+
+        if let (Mode::Render, None) = (&parsed.mode, &parsed.out) {
+            return Err("render needs --out <frame.png|frame.ppm|frame.ans>".into());
+        }
+        Ok(parsed)
+    }
+}
+
+fn parse_vec(value: &str) -> Result<Vec3, Box<dyn Error>> {
+    let split: Vec<&str> = value.split(',').collect();
+    if split.len() != 3 {
+        return Err("positions are x,y,z".into());
+    }
+    let (x, y, z) =
+        (split[0].parse::<Float>()?, split[1].parse::<Float>()?, split[2].parse::<Float>()?);
+    Ok(Vec3::cons(x, y, z))
+}
+
+/// sizes are given as columns x rows, like the terminal reports them
+fn parse_size(value: &str) -> Result<(Int, Int), Box<dyn Error>> {
+    let (width, height) = value.split_once('x').ok_or("sizes are <width>x<height>")?;
+    Ok((width.parse()?, height.parse()?))
+}
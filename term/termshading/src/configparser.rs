@@ -1,15 +1,20 @@
 
 
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::error::Error;
 
 use crate::entities::{Feature, Orbit, OrbitalParams, PlanetParams, Ring, SpacialReference};
-use crate::entities::{Planet, System};
-use crate::math::{orbital_cartesian_transformation, Vec3};
-use crate::utils::flash_error;
-use crate::{Float, Int};
+use crate::entities::{Belt, Comet, Planet, System};
+use crate::renderer::TextureData;
+use crate::trajectory::{units, Frame, Trajectory, MARKERRADIUS};
+use crate::controls::Keybindings;
+use crate::math::{mean_to_true, orbital_cartesian_transformation, state_to_elements, Vec3};
+use crate::physics::GRAVITATIONAL;
+use crate::utils::{flash_error, julian_day};
+use crate::{Float, Int, AU, RADIUSSCALE, SCENEEPOCH, SUNGM, TAU};
 
 
 
@@ -26,6 +31,8 @@ pub const URANUSPATH: &str = "../planet_textures/uranus_map.txt";
 pub const NEPTUNEPATH: &str = "../planet_textures/neptune_map.txt";
 pub const PLUTOPATH: &str = "../planet_textures/pluto_map.txt";
 
+const NAMEDELEMENTS: [&str; 10] = ["a", "qr", "ec", "in", "om", "w", "ta", "ma", "tp", "epoch"];
+
 
 
 pub struct TargetFeature<'t> {
@@ -43,7 +50,11 @@ pub fn parse_config(file_path: &str, system: &mut System) -> Result<(), Box<dyn
     print!("\x1b[2J");
     print!("\x1b[H");
     let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
+    parse_system(BufReader::new(file), system)
+}
+
+/// fills the system from config lines read from anywhere, flashing lines that do not parse
+pub fn parse_system(reader: impl BufRead, system: &mut System) -> Result<(), Box<dyn Error>> {
     let error_delay = 3000;
 
     for line in reader.lines() {
@@ -75,14 +86,33 @@ pub fn parse_config(file_path: &str, system: &mut System) -> Result<(), Box<dyn
                 Err(err) => flash_error(err, error_delay),
             }
         }
+        else if line.starts_with("belt") {
+            match parse_belt(&line) {
+                Ok(belt) => system.add_belt(belt),
+                Err(err) => flash_error(err, error_delay),
+            }
+        }
+        else if line.starts_with("comet") {
+            match parse_comet(&line) {
+                Ok(comet) => system.add_planet(comet),
+                Err(err) => flash_error(err, error_delay),
+            }
+        }
+        else if line.starts_with("trajectory") {
+            match parse_trajectory(&line) {
+                Ok(marker) => system.add_planet(marker),
+                Err(err) => flash_error(err, error_delay),
+            }
+        }
         else if line.starts_with("moon") {
             match parse_moon(&line) {
                 Ok(targfeat) => system.add_feature(targfeat.target, targfeat.feature),
                 Err(err) => flash_error(err, error_delay),
             }
         }
-        
-        if line.contains("orbital") || line.contains("planet") {
+
+        // bodies about the sun also get their path drawn
+        if line.starts_with("planet") || line.starts_with("comet") {
             match parse_parent_orbit(&line) {
                 Ok(targfeat) => system.add_feature(targfeat.target, targfeat.feature),
                 Err(err) => flash_error(err, error_delay),
@@ -99,9 +129,10 @@ fn parse_planet(data: &str) -> Result<Planet, Box<dyn Error>> {
     let mut rad = None;
     let mut params = None;
     let mut lightsource = false;
+    let mut gm = None;
 
     for token in data.split_whitespace() {
-        if token == "planet" {
+        if token == "planet" || token == "comet" {
             continue;
         }
         else if name.is_none() {
@@ -120,6 +151,9 @@ fn parse_planet(data: &str) -> Result<Planet, Box<dyn Error>> {
                 "orbital" => {
                     location_orbital(value, &mut loc)?;
                 }
+                "state" => {
+                    location_state(value, &mut loc)?;
+                }
                 "cartesian" => {
                     location_cartesian(value, &mut loc)?;
                 }
@@ -132,21 +166,103 @@ fn parse_planet(data: &str) -> Result<Planet, Box<dyn Error>> {
                 "lightsource" => {
                     lightsource = value.parse::<bool>()?;
                 }
+                "gm" => {
+                    gm = Some(value.parse::<f64>()?);
+                }
+                "mass" => {
+                    gm = Some(value.parse::<f64>()? * GRAVITATIONAL);
+                }
                 _ => {}
             }
         }
     }
 
+    if loc.is_none() {
+        loc = parse_named_orbit(data)?.map(|orbit| orbital_cartesian_transformation(&orbit));
+    }
+
     if let (Some(name), Some(loc), Some(rad)) = (name, loc, rad) {
         let texture = get_texture(name);
-        Ok(Planet::cons(name.to_owned(), loc, rad, texture, lightsource, params))
+        let mut planet = Planet::cons(name.to_owned(), loc, rad, texture, lightsource, params);
+        planet.gm = gm;
+        Ok(planet)
     }
     else {
         Err("missing requirements".into())
     }
 }
 
-fn parse_spaceref(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
+/// a planet line for the nucleus plus `coma=` and `tail=`, their sizes in km at 1 au
+fn parse_comet(data: &str) -> Result<Planet, Box<dyn Error>> {
+    let mut planet = parse_planet(data)?;
+    let mut comet = Comet::cons(20000.0, 2e7);
+    for token in data.split_whitespace() {
+        if let Some(value) = token.strip_prefix("coma=") {
+            comet.coma = value.parse()?;
+        }
+        else if let Some(value) = token.strip_prefix("tail=") {
+            comet.tail = value.parse()?;
+        }
+    }
+    planet.texture = Some(TextureData::from(MOONPATH));
+    planet.features.push(Feature::Comet(comet));
+    Ok(planet)
+}
+
+/// `trajectory <name> <csv path>` with optional `frame=`, `units=` and `center=`, defaulting
+/// to ecliptic km about the sun. the craft becomes a small body so it can be targeted
+fn parse_trajectory(data: &str) -> Result<Planet, Box<dyn Error>> {
+    let tokens: Vec<&str> = data.split_whitespace().skip(1).collect();
+    if tokens.len() < 2 {
+        return Err("trajectory needs a name and a csv file".into());
+    }
+    let (mut frame, mut scale, mut center) = (Frame::Ecliptic, (1.0, 1.0), None);
+    for token in &tokens[2..] {
+        match token.split_once('=') {
+            Some(("frame", value)) => {
+                frame = Frame::parse(value).ok_or(format!("no frame named {}", value))?;
+            }
+            Some(("units", value)) => {
+                scale = units(value).ok_or(format!("no units named {}", value))?;
+            }
+            Some(("center", value)) => center = Some(value.to_owned()),
+            _ => {}
+        }
+    }
+    let mut trajectory = Trajectory::load(tokens[1], frame, scale)?;
+    if center.is_some() {
+        // spread out like moons are, or a path about a planet would sit inside it
+        trajectory.scale(1.0 / RADIUSSCALE);
+    }
+    trajectory.center = center;
+    let loc = trajectory.position(0.0);
+    let mut marker = Planet::cons(tokens[0].to_owned(), loc, MARKERRADIUS, None, false, None);
+    marker.features.push(Feature::Trajectory(trajectory));
+    Ok(marker)
+}
+
+/// `belt <inner au> <outer au> <count> <inclination spread degrees> <seed>`, with an optional
+/// `motion=false` to hold the belt still
+fn parse_belt(data: &str) -> Result<Belt, Box<dyn Error>> {
+    let tokens: Vec<&str> = data.split_whitespace().skip(1).collect();
+    if tokens.len() < 5 {
+        return Err("belt needs inner, outer, count, inclination spread and seed".into());
+    }
+    let (inner, outer) = (tokens[0].parse::<Float>()? * AU, tokens[1].parse::<Float>()? * AU);
+    if inner <= 0.0 || outer < inner {
+        return Err(format!("belt at {} needs 0 < inner <= outer", tokens[0]).into());
+    }
+    let spread = tokens[3].parse::<Float>()?.to_radians();
+    let mut belt = Belt::generate(inner, outer, tokens[2].parse()?, spread, tokens[4].parse()?);
+    for token in &tokens[5..] {
+        if let Some(value) = token.strip_prefix("motion=") {
+            belt.moving = value.parse()?;
+        }
+    }
+    Ok(belt)
+}
+
+fn parse_spaceref(data: &str) -> Result<TargetFeature<'_>, Box<dyn Error>> {
     let mut length = None;
     let mut target = None;
     for token in data.split_whitespace() {
@@ -172,7 +288,7 @@ fn parse_spaceref(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
     }
 }
 
-fn parse_orbit(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
+fn parse_orbit(data: &str) -> Result<TargetFeature<'_>, Box<dyn Error>> {
     let mut target: Option<&str> = None;
     let mut orbit: Option<Orbit> = None;
 
@@ -196,7 +312,7 @@ fn parse_orbit(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
     }
 }
 
-fn parse_ring(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
+fn parse_ring(data: &str) -> Result<TargetFeature<'_>, Box<dyn Error>> {
     let mut target = None;
     let mut rad = None;
     let mut depth = None;
@@ -234,13 +350,14 @@ fn parse_ring(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
     }
 }
 
-fn parse_moon(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
+fn parse_moon(data: &str) -> Result<TargetFeature<'_>, Box<dyn Error>> {
     let mut name = None;
     let mut target = None;
     let mut loc = None;
     let mut rad = None;
     let mut params = None;
     let mut lightsource = false;
+    let mut gm = None;
 
     for token in data.split_whitespace() {
         if token == "moon" {
@@ -277,6 +394,12 @@ fn parse_moon(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
                 "target" => {
                     target = Some(value);
                 }
+                "gm" => {
+                    gm = Some(value.parse::<f64>()?);
+                }
+                "mass" => {
+                    gm = Some(value.parse::<f64>()? * GRAVITATIONAL);
+                }
                 _ => {}
             }
         }
@@ -284,19 +407,20 @@ fn parse_moon(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
 
     if let (Some(name), Some(loc), Some(rad), Some(target)) = (name, loc, rad, target) {
         let texture = get_texture(name);
-        Ok(TargetFeature::cons(target, Feature::Moon(
-            Planet::cons(name.to_owned(), loc, rad, texture, lightsource, params))))
+        let mut moon = Planet::cons(name.to_owned(), loc, rad, texture, lightsource, params);
+        moon.gm = gm;
+        Ok(TargetFeature::cons(target, Feature::Moon(moon)))
     }
     else {
         Err("missing requirements".into())
     }
 }
 
-fn parse_parent_orbit(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
+fn parse_parent_orbit(data: &str) -> Result<TargetFeature<'_>, Box<dyn Error>> {
     let mut target = None;
     let mut orbit = None;
     for token in data.split_whitespace() {
-        if token == "planet" {
+        if token == "planet" || token == "comet" {
             continue;
         }
         else if target.is_none() {
@@ -305,6 +429,12 @@ fn parse_parent_orbit(data: &str) -> Result<TargetFeature, Box<dyn Error>> {
         else if let Some(value) = token.strip_prefix("orbital=") {
             parse_orbit_specific(value, &mut orbit)?;
         }
+        else if let Some(value) = token.strip_prefix("state=") {
+            parse_state_specific(value, &mut orbit)?;
+        }
+    }
+    if orbit.is_none() {
+        orbit = parse_named_orbit(data)?;
     }
 
     if let (Some(target), Some(mut orbit)) = (target, orbit) {
@@ -321,18 +451,105 @@ fn parse_orbit_specific(value: &str, orbit: &mut Option<Orbit>) -> Result<(), Bo
     if split.len() < 6 {
         return Err("too few arguments".into());
     }
-    let semimajor = split[0].parse::<Float>()?;
+    let mut semimajor = split[0].parse::<Float>()?;
     let eccentricity = split[1].parse::<Float>()?;
+    if eccentricity < 0.0 {
+        return Err("eccentricity can not be negative".into());
+    }
+    // open orbits take the periapsis distance, though a negative hyperbolic axis is fine too
+    if eccentricity > 1.0 && semimajor < 0.0 {
+        semimajor *= 1.0 - eccentricity;
+    }
+    if semimajor <= 0.0 {
+        return Err("orbits need a positive size".into());
+    }
     let inclination = split[2].parse::<Float>()?.to_radians();
     let longitdueofascnode = split[3].parse::<Float>()?.to_radians();
     let argofperiapsis = split[4].parse::<Float>()?.to_radians();
     let trueanomaly = split[5].parse::<Float>()?.to_radians();
+    if 1.0 + eccentricity * trueanomaly.cos() <= 0.0 {
+        return Err("true anomaly is past the asymptote of an open orbit".into());
+    }
     let bary = Vec3::cons(0, 0, 0);
     *orbit = Some(Orbit::cons(OrbitalParams::cons(semimajor, eccentricity, inclination,
         longitdueofascnode, argofperiapsis, trueanomaly), bary, true));
     Ok(())
 }
 
+/// horizons style elements as separate tokens: a= or qr= in km, ec=, in=, om= and w= in
+/// degrees, and the anomaly as one of ta= or ma= in degrees or tp= as a julian day. ma= holds
+/// at epoch=, a julian day or date defaulting to the scene epoch, and both it and tp= are
+/// carried to the scene epoch with the mean motion about the sun
+fn parse_named_orbit(data: &str) -> Result<Option<Orbit>, Box<dyn Error>> {
+    let values: HashMap<&str, &str> = data.split_whitespace()
+        .filter_map(|token| token.split_once('='))
+        .filter(|(key, _)| NAMEDELEMENTS.contains(key))
+        .collect();
+    if values.is_empty() {
+        return Ok(None);
+    }
+    let number = |key: &str| -> Result<Option<f64>, Box<dyn Error>> {
+        Ok(values.get(key).map(|value| value.parse::<f64>()).transpose()?)
+    };
+    let angle = |key: &str| -> Result<Float, Box<dyn Error>> {
+        Ok(number(key)?.unwrap_or(0.0).to_radians() as Float)
+    };
+
+    let eccentricity = number("ec")?.ok_or("named elements need ec=")?;
+    let size = match (number("a")?, number("qr")?) {
+        (_, Some(periapsis)) if eccentricity < 1.0 => periapsis / (1.0 - eccentricity),
+        (_, Some(periapsis)) => periapsis,
+        (Some(semimajor), None) if eccentricity < 1.0 => semimajor,
+        (Some(semimajor), None) if semimajor < 0.0 => semimajor * (1.0 - eccentricity),
+        _ => return Err("named elements need a=, or qr= for open orbits".into()),
+    };
+    if eccentricity < 0.0 || size <= 0.0 {
+        return Err("named elements need a positive size and eccentricity".into());
+    }
+    let mut params = OrbitalParams::cons(size as Float, eccentricity as Float,
+        angle("in")?, angle("om")?, angle("w")?, 0.0);
+
+    let scene = julian_day(SCENEEPOCH)?;
+    let meanmotion = params.meanmotion(SUNGM) as f64;
+    let mean = match (number("ta")?, number("ma")?, number("tp")?) {
+        (Some(_), None, None) => None,
+        (None, Some(mean), None) => {
+            let epoch = values.get("epoch").map(|epoch| julian_day(epoch)).transpose()?;
+            Some(mean.to_radians() + meanmotion * (scene - epoch.unwrap_or(scene)) * 86400.0)
+        }
+        (None, None, Some(periapsis)) => Some(meanmotion * (scene - periapsis) * 86400.0),
+        _ => return Err("named elements need exactly one of ta=, ma= or tp=".into()),
+    };
+    params.trueanomaly = match mean {
+        // wrapped while still in f64, whole revolutions would eat the precision otherwise
+        Some(mean) if eccentricity < 1.0 => {
+            mean_to_true(mean.rem_euclid(TAU as f64) as Float, params.eccentricity)
+        }
+        Some(mean) => mean_to_true(mean as Float, params.eccentricity),
+        None => angle("ta")?,
+    };
+    if 1.0 + params.eccentricity * params.trueanomaly.cos() <= 0.0 {
+        return Err("true anomaly is past the asymptote of an open orbit".into());
+    }
+    Ok(Some(Orbit::cons(params, Vec3::cons(0, 0, 0), true)))
+}
+
+/// heliocentric position and velocity in km and km/s, turned into elements about the sun
+fn parse_state_specific(value: &str, orbit: &mut Option<Orbit>) -> Result<(), Box<dyn Error>> {
+    let split: Vec<Float> = value.split(',').map(|part| part.parse::<Float>())
+        .collect::<Result<_, _>>()?;
+    if split.len() < 6 {
+        return Err("state needs x,y,z,vx,vy,vz".into());
+    }
+    let pos = Vec3::cons(split[0], split[1], split[2]);
+    let vel = Vec3::cons(split[3], split[4], split[5]);
+    if pos.length() == 0.0 || pos.cross_prod(&vel).length() == 0.0 {
+        return Err("state has no angular momentum about the sun".into());
+    }
+    *orbit = Some(Orbit::cons(state_to_elements(pos, vel, SUNGM), Vec3::cons(0, 0, 0), true));
+    Ok(())
+}
+
 fn parse_ring_specific(value: &str, rad: &mut Option<f32>, depth: &mut Option<f32>) -> Result<(), Box<dyn Error>> {
     let parts: Vec<&str> = value.split(',').collect();
     *rad = Some(parts[0].parse::<Float>()?);
@@ -359,6 +576,13 @@ fn location_orbital(value: &str, loc: &mut Option<Vec3>) -> Result<(), Box<dyn E
     Ok(())
 }
 
+fn location_state(value: &str, loc: &mut Option<Vec3>) -> Result<(), Box<dyn Error>> {
+    let mut orbit: Option<Orbit> = None;
+    parse_state_specific(value, &mut orbit)?;
+    *loc = Some(orbital_cartesian_transformation(&orbit.unwrap()));
+    Ok(())
+}
+
 fn location_polar(value: &str, loc: &mut Option<Vec3>) -> Result<(), Box<dyn Error>> {
     let split: Vec<&str> = value.split(',').collect();
     if split.len() < 2 {
@@ -405,9 +629,23 @@ pub struct Config {
     height: Int, width: Int,
     fov: Float,
     render_refs: bool,
+    render_lagrange: bool,
     render_orbits: bool,
+    render_labels: bool,
+    label_moons: bool,
+    render_panel: bool,
+    panel_width: Int,
+    render_profile: bool,
+    target_fps: Float,
+    supersample: (Int, Int),
+    nbody: bool,
+    burn_dv: f64,
+    launch_speed: Float,
+    transfer_days: f64,
+    epoch: Option<String>,
     termcharaspect: Float,
     orbital_distance: Float,
+    keybindings: Keybindings,
 }
 
 impl Default for Config {
@@ -417,9 +655,23 @@ impl Default for Config {
             width: 40,
             fov: 20.0,
             render_refs: false,
+            render_lagrange: false,
             render_orbits: false,
+            render_labels: true,
+            label_moons: false,
+            render_panel: true,
+            panel_width: 30,
+            render_profile: false,
+            target_fps: 60.0,
+            supersample: (1, 1),
+            nbody: false,
+            burn_dv: 0.1,
+            launch_speed: 1.0,
+            transfer_days: 0.0,
+            epoch: None,
             termcharaspect: 2.0,
             orbital_distance: 400.0,
+            keybindings: Keybindings::default(),
         }
     }
 }
@@ -445,22 +697,112 @@ impl Config {
         self.render_refs
     }
 
+    pub fn render_lagrange(&self) -> bool {
+        self.render_lagrange
+    }
+
     pub fn orbital_distance(&self) -> Float {
         self.orbital_distance
     }
 
+    pub fn keybindings(&self) -> &Keybindings {
+        &self.keybindings
+    }
+
     pub fn render_orbits(&self) -> bool {
         self.render_orbits
     }
 
+    pub fn render_labels(&self) -> bool {
+        self.render_labels
+    }
+
+    pub fn label_moons(&self) -> bool {
+        self.label_moons
+    }
+
+    pub fn render_panel(&self) -> bool {
+        self.render_panel
+    }
+
+    /// columns kept free of the scene for the target panel, zero while it is hidden
+    pub fn panel_reserve(&self) -> Int {
+        if self.render_panel { self.panel_width } else { 0 }
+    }
+
+    pub fn target_fps(&self) -> Float {
+        self.target_fps
+    }
+
+    pub fn supersample(&self) -> Option<(Int, Int)> {
+        Some(self.supersample).filter(|samples| *samples != (1, 1))
+    }
+
+    pub fn set_supersample(&mut self, sampx: Int, sampy: Int) {
+        self.supersample = (sampx.max(1), sampy.max(1));
+    }
+
+    /// date the planets are placed for, from the built in element table
+    pub fn epoch(&self) -> Option<&str> {
+        self.epoch.as_deref()
+    }
+
+    pub fn nbody(&self) -> bool {
+        self.nbody
+    }
+
+    /// km/s each probe burn adds
+    pub fn burn_dv(&self) -> f64 {
+        self.burn_dv
+    }
+
+    /// km/s a new probe is thrown along the view with, on top of a circular orbit
+    pub fn launch_speed(&self) -> Float {
+        self.launch_speed
+    }
+
+    /// flight time of lambert transfers, zero to match the hohmann transfer
+    pub fn transfer_days(&self) -> f64 {
+        self.transfer_days
+    }
+
+    pub fn render_profile(&self) -> bool {
+        self.render_profile
+    }
+
     pub fn toggle_refs(&mut self) {
         self.render_refs = !self.render_refs;
     }
 
+    pub fn toggle_lagrange(&mut self) {
+        self.render_lagrange = !self.render_lagrange;
+    }
+
     pub fn toggle_orbits(&mut self) {
         self.render_orbits = !self.render_orbits;
     }
 
+    pub fn toggle_labels(&mut self) {
+        self.render_labels = !self.render_labels;
+    }
+
+    pub fn toggle_panel(&mut self) {
+        self.render_panel = !self.render_panel;
+    }
+
+    pub fn toggle_profiler(&mut self) {
+        self.render_profile = !self.render_profile;
+    }
+
+    pub fn set_fov(&mut self, fov: Float) {
+        self.fov = fov;
+    }
+
+    pub fn set_size(&mut self, width: Int, height: Int) {
+        self.width = width;
+        self.height = height;
+    }
+
     pub fn modify_fov(&mut self, direc: i32) {
         self.fov += direc as Float;
     }
@@ -473,6 +815,7 @@ pub fn general_config(file_path: &str) -> Result<Config, Box<dyn Error>> {
     let reader = BufReader::new(file);
 
     let mut config: Config = Config::default();
+    let mut section = "general".to_owned();
 
     for line in reader.lines() {
         let line = line?.trim().to_string();
@@ -480,6 +823,15 @@ pub fn general_config(file_path: &str) -> Result<Config, Box<dyn Error>> {
             continue;
         }
 
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name.to_owned();
+            continue;
+        }
+        if section == "keybindings" {
+            config.keybindings.parse_line(&line)?;
+            continue;
+        }
+
         if let Some(value) = line.strip_prefix("height=") {
             config.height = value.parse()?;
         }
@@ -495,7 +847,71 @@ pub fn general_config(file_path: &str) -> Result<Config, Box<dyn Error>> {
         else if let Some(value) = line.strip_prefix("orbital_distance=") {
             config.orbital_distance = value.parse()?;
         }
+        else if let Some(value) = line.strip_prefix("labels=") {
+            config.render_labels = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("label_moons=") {
+            config.label_moons = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("panel=") {
+            config.render_panel = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("panel_width=") {
+            config.panel_width = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("profiler=") {
+            config.render_profile = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("target_fps=") {
+            config.target_fps = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("epoch=") {
+            config.epoch = Some(value.to_owned());
+        }
+        else if let Some(value) = line.strip_prefix("nbody=") {
+            config.nbody = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("burn_dv=") {
+            config.burn_dv = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("launch_speed=") {
+            config.launch_speed = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("transfer_days=") {
+            config.transfer_days = value.parse()?;
+        }
+        else if let Some(value) = line.strip_prefix("supersample=") {
+            let (sampx, sampy) = value.split_once('x').ok_or("supersample is <across>x<down>")?;
+            config.set_supersample(sampx.parse()?, sampy.parse()?);
+        }
     }
 
     Ok(config)
 }
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PI;
+
+    fn anomaly(line: &str) -> Float {
+        parse_named_orbit(line).unwrap().unwrap().params.trueanomaly
+    }
+
+    #[test]
+    fn named_elements() {
+        let earthlike = "planet x 1 a=1.496E+8 ec=0.0167 in=0 om=0 w=0";
+        let period = TAU as f64 / (SUNGM as f64 / 1.496e8f64.powi(3)).sqrt() / 86400.0;
+        let scene = julian_day(SCENEEPOCH).unwrap();
+
+        assert!(anomaly(&format!("{} ma=0", earthlike)).abs() < 1e-4);
+        let half = format!("{} tp={}", earthlike, scene - period / 2.0);
+        assert!((anomaly(&half) - PI).abs() < 1e-2);
+        let later = format!("{} ma=90 epoch={}", earthlike, scene - period);
+        assert!((anomaly(&later) - anomaly(&format!("{} ma=90", earthlike))).abs() < 1e-3);
+        assert!(parse_named_orbit(earthlike).is_err());
+        assert!(parse_named_orbit("planet x 1 orbital=1,0,0,0,0,0").unwrap().is_none());
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::entities::CameraMode;
+use crate::probe::Burn;
+use crate::utils::julian_day;
+use crate::Float;
+
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Forward,
+    Back,
+    Left,
+    Right,
+    Up,
+    Down,
+    RotateLeft,
+    RotateRight,
+    TiltUp,
+    TiltDown,
+    RollLeft,
+    RollRight,
+    ToggleFreeflight,
+    SpeedUp,
+    SpeedDown,
+    Goto(String),
+    Home,
+    ToggleRefs,
+    ToggleLagrange,
+    ToggleOrbits,
+    ToggleLabels,
+    TogglePanel,
+    NextTarget,
+    PrevTarget,
+    Camera(CameraMode),
+    NextCamera,
+    ZoomIn,
+    ZoomOut,
+    WarpUp,
+    WarpDown,
+    SetWarp(Float),
+    Pause,
+    FovUp,
+    FovDown,
+    ToggleRecording,
+    ToggleProfiler,
+    ToggleNbody,
+    SpawnProbe,
+    Burn(Burn),
+    PickTransfer,
+    /// departure as a julian day, or the scene's own date
+    Lambert(Option<f64>),
+    Quit,
+}
+
+impl Action {
+    pub fn is_movement(&self) -> bool {
+        matches!(self,
+            Action::Forward | Action::Back | Action::Left | Action::Right | Action::Up
+            | Action::Down | Action::RotateLeft | Action::RotateRight | Action::TiltUp
+            | Action::TiltDown | Action::RollLeft | Action::RollRight
+        )
+    }
+
+    pub fn parse(name: &str) -> Result<Action, Box<dyn Error>> {
+        if let Some(target) = name.strip_prefix("goto:") {
+            if target.is_empty() {
+                return Err("goto is missing a target".into());
+            }
+            return Ok(Action::Goto(target.to_owned()));
+        }
+        if let Some(warp) = name.strip_prefix("warp:") {
+            return Ok(Action::SetWarp(warp.parse()?));
+        }
+        if let Some(burn) = name.strip_prefix("burn:") {
+            return Burn::parse(burn).map(Action::Burn)
+                .ok_or_else(|| format!("unknown burn {}", burn).into());
+        }
+        if let Some(date) = name.strip_prefix("lambert:") {
+            return Ok(Action::Lambert(Some(julian_day(date)?)));
+        }
+        if let Some(mode) = name.strip_prefix("camera:") {
+            return CameraMode::parse(mode).map(Action::Camera)
+                .ok_or_else(|| format!("unknown camera mode {}", mode).into());
+        }
+        match name {
+            "translate:forward" => Ok(Action::Forward),
+            "translate:back" => Ok(Action::Back),
+            "translate:left" => Ok(Action::Left),
+            "translate:right" => Ok(Action::Right),
+            "translate:up" => Ok(Action::Up),
+            "translate:down" => Ok(Action::Down),
+            "rotate:left" => Ok(Action::RotateLeft),
+            "rotate:right" => Ok(Action::RotateRight),
+            "tilt:up" => Ok(Action::TiltUp),
+            "tilt:down" => Ok(Action::TiltDown),
+            "roll:left" => Ok(Action::RollLeft),
+            "roll:right" => Ok(Action::RollRight),
+            "toggle_freeflight" => Ok(Action::ToggleFreeflight),
+            "speed+" => Ok(Action::SpeedUp),
+            "speed-" => Ok(Action::SpeedDown),
+            "home" => Ok(Action::Home),
+            "toggle_refs" => Ok(Action::ToggleRefs),
+            "toggle_lagrange" => Ok(Action::ToggleLagrange),
+            "toggle_orbits" => Ok(Action::ToggleOrbits),
+            "toggle_labels" => Ok(Action::ToggleLabels),
+            "toggle_panel" => Ok(Action::TogglePanel),
+            "target+" => Ok(Action::NextTarget),
+            "target-" => Ok(Action::PrevTarget),
+            "camera+" => Ok(Action::NextCamera),
+            "zoom+" => Ok(Action::ZoomIn),
+            "zoom-" => Ok(Action::ZoomOut),
+            "warp+" => Ok(Action::WarpUp),
+            "warp-" => Ok(Action::WarpDown),
+            "pause" => Ok(Action::Pause),
+            "fov+" => Ok(Action::FovUp),
+            "fov-" => Ok(Action::FovDown),
+            "toggle_recording" => Ok(Action::ToggleRecording),
+            "toggle_profiler" => Ok(Action::ToggleProfiler),
+            "toggle_nbody" => Ok(Action::ToggleNbody),
+            "spawn_probe" => Ok(Action::SpawnProbe),
+            "pick_transfer" => Ok(Action::PickTransfer),
+            "lambert" => Ok(Action::Lambert(None)),
+            "quit" => Ok(Action::Quit),
+            _ => Err(format!("unknown action {}", name).into()),
+        }
+    }
+}
+
+pub struct Keybindings {
+    bindings: HashMap<char, Action>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Keybindings {
+        let defaults = [
+            ('w', "translate:forward"), ('s', "translate:back"),
+            ('a', "translate:left"), ('d', "translate:right"),
+            ('W', "translate:up"), ('S', "translate:down"),
+            ('q', "rotate:left"), ('e', "rotate:right"),
+            ('r', "tilt:up"), ('f', "tilt:down"),
+            ('Q', "roll:left"), ('E', "roll:right"), ('g', "toggle_freeflight"),
+            ('[', "speed-"), (']', "speed+"),
+            ('1', "goto:mercury"), ('2', "goto:venus"), ('3', "goto:earth"),
+            ('4', "goto:mars"), ('5', "goto:jupiter"), ('6', "goto:saturn"),
+            ('7', "goto:uranus"), ('8', "goto:neptune"), ('9', "goto:pluto"),
+            ('0', "home"),
+            ('n', "toggle_refs"), ('N', "toggle_lagrange"), ('m', "toggle_orbits"),
+            ('l', "toggle_labels"),
+            ('i', "toggle_panel"), ('t', "target+"), ('T', "target-"),
+            ('c', "camera+"), ('v', "camera:free"), ('z', "zoom+"), ('x', "zoom-"),
+            ('+', "warp+"), ('-', "warp-"), (' ', "pause"),
+            (',', "fov+"), ('.', "fov-"),
+            ('R', "toggle_recording"), ('o', "toggle_profiler"), ('b', "toggle_nbody"),
+            ('p', "quit"),
+            ('y', "spawn_probe"), ('u', "burn:prograde"), ('j', "burn:retrograde"),
+            ('k', "burn:normal"), ('K', "burn:antinormal"),
+            ('h', "burn:radial_out"), ('H', "burn:radial_in"),
+            ('X', "pick_transfer"), ('L', "lambert"),
+        ];
+        let mut keybindings = Keybindings { bindings: HashMap::new() };
+        defaults.iter().for_each(|(key, name)| {
+            keybindings.bind(*key, Action::parse(name).unwrap());
+        });
+        keybindings
+    }
+}
+
+impl Keybindings {
+    pub fn bind(&mut self, key: char, action: Action) {
+        self.bindings.insert(key, action);
+    }
+
+    pub fn unbind(&mut self, key: char) {
+        self.bindings.remove(&key);
+    }
+
+    pub fn resolve(&self, key: char) -> Option<&Action> {
+        self.bindings.get(&key)
+    }
+
+    /// parses a single `<key> <action>` line from the keybindings section. the action `none`
+    /// removes whatever default was bound to the key
+    pub fn parse_line(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
+        let mut tokens = line.split_whitespace();
+        let (Some(key), Some(name)) = (tokens.next(), tokens.next()) else {
+            return Err(format!("malformed keybinding {}", line).into());
+        };
+        let key = parse_key(key)?;
+        if name == "none" {
+            self.unbind(key);
+        }
+        else {
+            self.bind(key, Action::parse(name)?);
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_key(key: &str) -> Result<char, Box<dyn Error>> {
+    if key == "space" {
+        return Ok(' ');
+    }
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(chr), None) => Ok(chr),
+        _ => Err(format!("keys must be a single character, got {}", key).into()),
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rebind() {
+        let mut keybindings = Keybindings::default();
+        keybindings.parse_line("z translate:forward").unwrap();
+        keybindings.parse_line("w none").unwrap();
+        assert!(keybindings.resolve('z') == Some(&Action::Forward));
+        assert!(keybindings.resolve('w').is_none());
+    }
+
+    #[test]
+    fn goto() {
+        let keybindings = Keybindings::default();
+        assert!(keybindings.resolve('3') == Some(&Action::Goto("earth".to_owned())));
+        assert!(Action::parse("goto:").is_err());
+        assert!(Action::parse("fly").is_err());
+    }
+}
@@ -2,99 +2,423 @@
 
 
 use crate::configparser::Config;
+use crate::controls::Action;
 use crate::renderer::TextureData;
-use crate::{Float, Int, PI, TAU};
-use crate::math::Vec3;
+use crate::{Float, Int, AU, DISTANCESCALE, PI, RADIUSSCALE, SCENEEPOCH, SUNGM, TAU};
+use crate::math::{mean_to_true, orbital_cartesian_transformation, true_to_mean, Quat, Vec3};
+use crate::math::{perifocal_to_frame, Rng, PARABOLIC};
+use crate::physics::Physics;
+use crate::probe::{Attractor, Burn, Probe, PROBENAME};
+use crate::trajectory::{Trajectory, MARKERRADIUS};
+use crate::transfer::Transfer;
+use crate::utils::julian_day;
 
 
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    Free,
+    Follow,
+    Orbit,
+    LookAt,
+}
+
+impl CameraMode {
+    pub fn parse(name: &str) -> Option<CameraMode> {
+        match name {
+            "free" => Some(CameraMode::Free),
+            "follow" => Some(CameraMode::Follow),
+            "orbit" => Some(CameraMode::Orbit),
+            "lookat" => Some(CameraMode::LookAt),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Free => "free",
+            CameraMode::Follow => "follow",
+            CameraMode::Orbit => "orbit",
+            CameraMode::LookAt => "lookat",
+        }
+    }
+
+    fn next(&self) -> CameraMode {
+        match self {
+            CameraMode::Free => CameraMode::Follow,
+            CameraMode::Follow => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::LookAt,
+            CameraMode::LookAt => CameraMode::Free,
+        }
+    }
+}
+
 pub struct ViewModel {
     pub pos: Vec3,
-    pub rot: Float,
-    pub tilt: Float,
+    pub orient: Quat,
+    pub freeflight: bool,
     pub rotspeed: Float,
     pub transspeed: Float,
+    pub target: Option<String>,
+    pub mode: CameraMode,
+    anchor: Option<Vec3>,
+    orbitrad: Float,
+    orbitaz: Float,
+    orbitel: Float,
+    flight: Option<Flight>,
+}
+
+/// an animated goto. the path is a quadratic bezier from where the camera was to a fixed offset
+/// from the body, bent around anything in the way, and tracks the body if it moves meanwhile
+struct Flight {
+    body: String,
+    start: Vec3,
+    control: Vec3,
+    offset: Vec3,
+    startorient: Quat,
+    endorient: Quat,
+    elapsed: Float,
+    duration: Float,
 }
 
 impl ViewModel {
     pub fn new(pos: Vec3) -> ViewModel {
-        ViewModel { pos, rot: 0.0, tilt: 0.0, rotspeed: PI / 75.0, transspeed: 16.0 }
+        ViewModel {
+            pos, orient: Quat::identity(), freeflight: false, rotspeed: PI / 75.0,
+            transspeed: 16.0, target: None,
+            mode: CameraMode::Free, anchor: None, orbitrad: 0.0, orbitaz: 0.0, orbitel: 0.0,
+            flight: None,
+        }
     }
 
-    pub fn react(&mut self, inputs: &[char], system: &System, config: &mut Config) {
+    pub fn react(
+        &mut self, inputs: &[Action], system: &System, config: &mut Config, clock: &mut Clock
+    ) {
         inputs.iter().for_each(|input| {
+            if input.is_movement() {
+                self.flight = None;
+            }
             match input {
-                'W' => self.translate(Vec3::cons(0, 0, 1)),
-                'S' => self.translate(Vec3::cons(0, 0, -1)),
-                'a' => self.translate(Vec3::cons(0, -1, 0)),
-                'd' => self.translate(Vec3::cons(0, 1, 0)),
-                'w' => self.translate(Vec3::cons(1, 0, 0)),
-                's' => self.translate(Vec3::cons(-1, 0, 0)),
-                'q' => self.rotate(-1.0),
-                'e' => self.rotate(1.0),
-                'r' => self.tilt(1.0),
-                'f' => self.tilt(-1.0),
-                '[' => self.transspeed *= 0.5,
-                ']' => self.transspeed *= 2.0,
-                '1' => self.goto("mercury", system),
-                '2' => self.goto("venus", system),
-                '3' => self.goto("earth", system),
-                '4' => self.goto("mars", system),
-                '5' => self.goto("jupiter", system),
-                '6' => self.goto("saturn", system),
-                '7' => self.goto("uranus", system),
-                '8' => self.goto("neptune", system),
-                '9' => self.goto("pluto", system),
-                '0' => self.goto_default(system),
-                'n' => config.toggle_refs(),
-                'm' => config.toggle_orbits(),
-                ',' => config.modify_fov(1),
-                '.' => config.modify_fov(-1),
-                _ => {}
+                Action::RotateLeft if self.mode == CameraMode::Orbit => self.orbit(-1.0, 0.0),
+                Action::RotateRight if self.mode == CameraMode::Orbit => self.orbit(1.0, 0.0),
+                Action::TiltUp if self.mode == CameraMode::Orbit => self.orbit(0.0, 1.0),
+                Action::TiltDown if self.mode == CameraMode::Orbit => self.orbit(0.0, -1.0),
+                Action::Up => self.translate(Vec3::cons(0, 0, 1)),
+                Action::Down => self.translate(Vec3::cons(0, 0, -1)),
+                Action::Left => self.translate(Vec3::cons(0, -1, 0)),
+                Action::Right => self.translate(Vec3::cons(0, 1, 0)),
+                Action::Forward => self.translate(Vec3::cons(1, 0, 0)),
+                Action::Back => self.translate(Vec3::cons(-1, 0, 0)),
+                Action::RotateLeft => self.rotate(-1.0),
+                Action::RotateRight => self.rotate(1.0),
+                Action::TiltUp => self.tilt(1.0),
+                Action::TiltDown => self.tilt(-1.0),
+                Action::RollLeft => self.roll(-1.0),
+                Action::RollRight => self.roll(1.0),
+                Action::ToggleFreeflight => self.toggle_freeflight(),
+                Action::SpeedDown => self.transspeed *= 0.5,
+                Action::SpeedUp => self.transspeed *= 2.0,
+                Action::Goto(target) => self.goto(target, system),
+                Action::Home => self.goto_default(system),
+                Action::ToggleRefs => config.toggle_refs(),
+                Action::ToggleLagrange => config.toggle_lagrange(),
+                Action::ToggleOrbits => config.toggle_orbits(),
+                Action::ToggleLabels => config.toggle_labels(),
+                Action::TogglePanel => config.toggle_panel(),
+                Action::ToggleProfiler => config.toggle_profiler(),
+                Action::NextTarget => self.cycle_target(1, system),
+                Action::PrevTarget => self.cycle_target(-1, system),
+                Action::Camera(mode) => self.set_mode(*mode, system),
+                Action::NextCamera => self.set_mode(self.mode.next(), system),
+                Action::ZoomIn => self.zoom(0.8),
+                Action::ZoomOut => self.zoom(1.25),
+                Action::WarpUp => clock.modify_warp(10.0),
+                Action::WarpDown => clock.modify_warp(0.1),
+                Action::SetWarp(warp) => clock.set_warp(*warp),
+                Action::Pause => clock.toggle_pause(),
+                Action::FovUp => config.modify_fov(1),
+                Action::FovDown => config.modify_fov(-1),
+                Action::ToggleRecording | Action::ToggleNbody | Action::SpawnProbe
+                | Action::Burn(_) | Action::PickTransfer | Action::Lambert(_) | Action::Quit => {}
             };
         });
     }
 
     pub fn goto(&mut self, target: &str, system: &System) {
-        system.planets.iter().for_each(|planet| {
-            if planet.name == target {
-                self.pos = planet.loc + Vec3::cons(-100 - (planet.rad as Int * 2), 0, 0);
-                self.tilt = 0.0;
-                self.rot = 0.0;
-            }
-        });
+        if let Some(planet) = system.find(target) {
+            let offset = Vec3::cons(-100 - (planet.rad as Int * 2), 0, 0);
+            self.target = Some(planet.name.clone());
+            self.fly(planet, offset, 0.0, 0.0, system);
+        }
     }
 
     pub fn goto_default(&mut self, system: &System) {
-        system.planets.iter().for_each(|planet| {
-            if planet.name == "sun" {
-                self.pos = planet.loc + Vec3::cons(0, 0, planet.rad as Int * 3);
-                self.tilt = -PI / 2.0;
-                self.rot = PI / 2.0;
-            }
+        if let Some(sun) = system.find("sun") {
+            let offset = Vec3::cons(0, 0, sun.rad as Int * 3);
+            self.fly(sun, offset, PI / 2.0, -PI / 2.0, system);
+        }
+    }
+
+    /// skips the rest of any flight in progress and puts the camera at its destination
+    pub fn land(&mut self, system: &System) {
+        if let Some(flight) = &mut self.flight {
+            flight.elapsed = flight.duration;
+            self.fly_step(0.0, system);
+        }
+    }
+
+    pub fn flying(&self) -> bool {
+        self.flight.is_some()
+    }
+
+    fn fly(&mut self, body: &Planet, offset: Vec3, yaw: Float, pitch: Float, system: &System) {
+        let start = self.pos;
+        let end = body.loc + offset;
+        let distance = (end - start).length();
+        self.flight = Some(Flight {
+            body: body.name.clone(),
+            start,
+            control: flight_control(start, end, system),
+            offset,
+            startorient: self.orient,
+            endorient: Quat::yaw_pitch(yaw, pitch),
+            elapsed: 0.0,
+            duration: (1.0 + distance.max(1.0).log10() * 0.5).clamp(1.0, 4.0),
         });
     }
 
+    fn fly_step(&mut self, frametime: Float, system: &System) {
+        let Some(flight) = &mut self.flight else { return; };
+        flight.elapsed += frametime;
+        let progress = (flight.elapsed / flight.duration).min(1.0);
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        let end = match system.find(&flight.body) {
+            Some(body) => body.loc + flight.offset,
+            None => flight.start,
+        };
+
+        let remain = 1.0 - eased;
+        self.pos = flight.start * (remain * remain)
+            + flight.control * (2.0 * remain * eased)
+            + end * (eased * eased);
+        self.orient = flight.startorient.slerp(&flight.endorient, eased);
+
+        if progress >= 1.0 {
+            self.flight = None;
+            // re-lock whatever mode is active now that the camera has arrived
+            self.set_mode(self.mode, system);
+        }
+    }
+
+    pub fn cycle_target(&mut self, direc: Int, system: &System) {
+        let count = system.planets.len() as Int;
+        let current = self.target.as_ref().and_then(|target| {
+            system.planets.iter().position(|planet| &planet.name == target)
+        });
+        let next = match current {
+            Some(idx) => (idx as Int + direc).rem_euclid(count),
+            None if direc > 0 => 0,
+            None => count - 1,
+        };
+        self.target = Some(system.planets[next as usize].name.clone());
+        self.set_mode(self.mode, system);
+    }
+
+    /// switches camera mode, capturing the current offset from the target so nothing jumps.
+    /// the locked modes need a target and stay free without one
+    pub fn set_mode(&mut self, mode: CameraMode, system: &System) {
+        let target = self.target.as_ref().and_then(|target| system.find(target));
+        let Some(target) = target else {
+            self.mode = CameraMode::Free;
+            self.anchor = None;
+            return;
+        };
+        self.mode = mode;
+        self.anchor = Some(target.loc);
+        if mode == CameraMode::Orbit {
+            let offset = self.pos - target.loc;
+            self.orbitrad = offset.length().max(target.rad * 2.0);
+            self.orbitaz = offset.y.atan2(offset.x);
+            self.orbitel = (offset.z / offset.length().max(Float::EPSILON)).clamp(-1.0, 1.0).asin();
+        }
+    }
+
+    /// advances any goto flight, otherwise keeps the camera locked to the target after the
+    /// system has moved under it
+    pub fn update(&mut self, frametime: Float, system: &System) {
+        if self.flight.is_some() {
+            self.fly_step(frametime, system);
+            return;
+        }
+        if self.mode == CameraMode::Free { return; }
+        let target = self.target.as_ref().and_then(|target| system.find(target));
+        let Some(target) = target else {
+            self.mode = CameraMode::Free;
+            return;
+        };
+
+        match self.mode {
+            CameraMode::Follow => {
+                if let Some(anchor) = self.anchor {
+                    self.pos += target.loc - anchor;
+                }
+            }
+            CameraMode::Orbit => {
+                let (sinaz, cosaz) = self.orbitaz.sin_cos();
+                let (sinel, cosel) = self.orbitel.sin_cos();
+                let offset = Vec3::cons(cosel * cosaz, cosel * sinaz, sinel) * self.orbitrad;
+                self.pos = target.loc + offset;
+                self.look_at(target.loc);
+            }
+            CameraMode::LookAt => self.look_at(target.loc),
+            CameraMode::Free => {}
+        }
+        self.anchor = Some(target.loc);
+    }
+
+    pub fn look_at(&mut self, point: Vec3) {
+        let dir = point - self.pos;
+        let horizontal = (dir.x * dir.x + dir.y * dir.y).sqrt();
+        if horizontal < Float::EPSILON && dir.z.abs() < Float::EPSILON { return; }
+        self.orient = Quat::yaw_pitch(dir.y.atan2(dir.x), dir.z.atan2(horizontal));
+    }
+
+    /// heading of the view. blending in the up vector keeps it defined when looking straight
+    /// up or down, where the forward vector alone has no horizontal part
+    pub fn yaw(&self) -> Float {
+        let (sin, cos) = self.pitch().sin_cos();
+        let heading = self.orient.forward() * cos - self.orient.up() * sin;
+        heading.y.atan2(heading.x).rem_euclid(TAU)
+    }
+
+    pub fn pitch(&self) -> Float {
+        self.orient.forward().z.clamp(-1.0, 1.0).asin()
+    }
+
+    /// how far the view is rolled away from a level horizon
+    pub fn bank(&self) -> Float {
+        let forward = self.orient.forward();
+        let level = Quat::yaw_pitch(self.yaw(), self.pitch()).up();
+        let up = self.orient.up();
+        level.cross_prod(&up).inner_prod(&forward).atan2(level.inner_prod(&up))
+    }
+
+    fn orbit(&mut self, azimuth: Float, elevation: Float) {
+        self.orbitaz += azimuth * self.rotspeed;
+        self.orbitel += elevation * self.rotspeed;
+        self.orbitel = self.orbitel.clamp(-PI / 2.0 + 0.01, PI / 2.0 - 0.01);
+    }
+
+    fn zoom(&mut self, factor: Float) {
+        if self.mode == CameraMode::Orbit {
+            self.orbitrad *= factor;
+        }
+    }
+
     fn translate(&mut self, dir: Vec3) {
-        let mut transdir = dir * self.transspeed;
-        let rotation = Vec3::cons(0.0, -self.tilt, self.rot);
-        transdir.rotationxyz(rotation);
-        self.pos += transdir;
+        self.pos += self.orient.rotate(dir * self.transspeed);
     }
 
+    /// yaw turns about the world vertical unless in free flight, where every turn is about the
+    /// view's own axes and nothing is clamped
     fn rotate(&mut self, dir: Float) {
-        self.rot += dir * self.rotspeed;
-        if self.rot < 0.0 {
-            self.rot += TAU;
+        let angle = dir * self.rotspeed;
+        if self.freeflight {
+            self.orient = self.orient * Quat::axis_angle(Vec3::cons(0, 0, 1), angle);
         }
-        else if self.rot > TAU {
-            self.rot -= TAU;
+        else {
+            self.orient = Quat::axis_angle(Vec3::cons(0, 0, 1), angle) * self.orient;
         }
+        self.orient.normalize();
     }
 
     fn tilt(&mut self, dir: Float) {
-        self.tilt += dir * self.rotspeed;
-        self.tilt = self.tilt.clamp(-PI / 2.0, PI / 2.0);
+        let angle = dir * self.rotspeed;
+        if !self.freeflight {
+            let pitch = (self.pitch() + angle).clamp(-PI / 2.0, PI / 2.0);
+            self.orient = Quat::yaw_pitch(self.yaw(), pitch);
+            return;
+        }
+        self.orient = self.orient * Quat::axis_angle(Vec3::cons(0, 1, 0), -angle);
+        self.orient.normalize();
+    }
+
+    /// only in free flight, everywhere else the horizon is kept level
+    fn roll(&mut self, dir: Float) {
+        if !self.freeflight { return; }
+        self.orient = self.orient * Quat::axis_angle(Vec3::cons(1, 0, 0), dir * self.rotspeed);
+        self.orient.normalize();
+    }
+
+    /// leaving free flight levels the horizon again, keeping the direction of view
+    fn toggle_freeflight(&mut self) {
+        self.freeflight = !self.freeflight;
+        if !self.freeflight {
+            self.orient = Quat::yaw_pitch(self.yaw(), self.pitch());
+        }
+    }
+}
+
+/// picks the bezier control point for a flight. a straight path is kept unless it clips a
+/// body, in which case the middle of the curve is pushed out past the worst offender
+fn flight_control(start: Vec3, end: Vec3, system: &System) -> Vec3 {
+    let mid = (start + end) / 2.0;
+    let path = end - start;
+    let length = path.length();
+    if length < Float::EPSILON { return mid; }
+    let dir = path / length;
+
+    let mut control = mid;
+    let mut worst = 0.0;
+    system.planets.iter().for_each(|planet| {
+        let along = (planet.loc - start).inner_prod(&dir).clamp(0.0, length);
+        let mut away = start + dir * along - planet.loc;
+        let miss = away.length();
+        let clearance = planet.rad * 1.5;
+        if miss >= clearance || clearance - miss <= worst { return; }
+        worst = clearance - miss;
+        if miss < Float::EPSILON {
+            away = dir.cross_prod(&Vec3::cons(0, 0, 1));
+            if away.length() < Float::EPSILON {
+                away = Vec3::cons(1, 0, 0);
+            }
+        }
+        away.normalize();
+        // a quadratic bezier only gets halfway to its control point, so it goes twice as far
+        control = mid + away * (2.0 * worst);
+    });
+    control
+}
+
+/// how fast simulation time runs against the frame time, and whether it is stopped. the time
+/// itself is kept on the system
+pub struct Clock {
+    pub warp: Float,
+    pub paused: bool,
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock { warp: 1.0, paused: false }
+    }
+
+    /// returns how many seconds of simulation time the frame covers
+    pub fn tick(&mut self, frametime: Float) -> Float {
+        if self.paused { return 0.0; }
+        frametime * self.warp
+    }
+
+    pub fn modify_warp(&mut self, factor: Float) {
+        self.set_warp(self.warp * factor);
+    }
+
+    /// from a thousandth of real time for slow motion up to about three years a second
+    pub fn set_warp(&mut self, warp: Float) {
+        self.warp = warp.clamp(1e-3, 1e8);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
     }
 }
 
@@ -115,10 +439,14 @@ pub struct Planet {
     pub name: String,
     pub loc: Vec3,
     pub rad: Float,
+    pub physrad: Float,
     pub texture: Option<TextureData>,
     pub lightsource: bool,
     pub params: Option<PlanetParams>,
     pub features: Vec<Feature>,
+    pub parent: Option<String>,
+    /// gravitational parameter in km^3/s^2, only needed by the n-body integrator
+    pub gm: Option<f64>,
 }
 
 impl Planet {
@@ -127,18 +455,45 @@ impl Planet {
         lightsource: bool, params: Option<PlanetParams>
     ) -> Planet {
         Planet {
-            name, loc, rad, texture: texpath.map(TextureData::from),
-            lightsource, params, features: Vec::new(),
+            name, loc, rad, physrad: rad, texture: texpath.map(TextureData::from),
+            lightsource, params, features: Vec::new(), parent: None, gm: None,
         }
     }
 }
 
+impl Planet {
+    /// the unlit orbit added from the body's own `orbital=` elements, as opposed to any extra
+    /// `orbit` lines drawn around it
+    pub fn path(&self) -> Option<&Orbit> {
+        self.features.iter().find_map(|feature| match feature {
+            Feature::Orbit(orbit) if !orbit.apply_lighting => Some(orbit),
+            _ => None,
+        })
+    }
+
+    pub fn trajectory(&self) -> Option<&Trajectory> {
+        self.features.iter().find_map(|feature| match feature {
+            Feature::Trajectory(trajectory) => Some(trajectory),
+            _ => None,
+        })
+    }
+
+    fn path_mut(&mut self) -> Option<&mut Orbit> {
+        self.features.iter_mut().find_map(|feature| match feature {
+            Feature::Orbit(orbit) if !orbit.apply_lighting => Some(orbit),
+            _ => None,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub enum Feature {
     Orbit(Orbit),
     Ring(Ring),
     SpacialReference(SpacialReference),
     Moon(Planet),
+    Comet(Comet),
+    Trajectory(Trajectory),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -146,16 +501,46 @@ pub struct Orbit {
     pub params: OrbitalParams,
     pub barycenter: Vec3,
     pub apply_lighting: bool,
+    pub meanmotion: Float,
+    /// mean anomaly at the scene epoch. paths in the system are placed from this and the clock
+    /// rather than stepped on every frame, so rounding does not pile up
+    pub epochmean: f64,
 }
 
 impl Orbit {
+    /// elements are taken as heliocentric and in km here, before transform_mini shrinks them
     pub fn cons(params: OrbitalParams, barycenter: Vec3, lighting: bool) -> Orbit {
-        Orbit { params, barycenter, apply_lighting: lighting }
+        let meanmotion = params.meanmotion(SUNGM);
+        let epochmean = true_to_mean(params.trueanomaly, params.eccentricity) as f64;
+        Orbit { params, barycenter, apply_lighting: lighting, meanmotion, epochmean }
+    }
+
+    /// moves the elements on by `seconds` in one go, for copies worked ahead of the clock
+    pub fn advance(&mut self, seconds: Float) {
+        let eccentricity = self.params.eccentricity;
+        let mean = true_to_mean(self.params.trueanomaly, eccentricity) + self.meanmotion * seconds;
+        self.params.trueanomaly = mean_to_true(mean, eccentricity);
+    }
+
+    /// where the path has the body `time` seconds after the scene epoch
+    pub fn at(&mut self, time: f64) {
+        let mut mean = self.epochmean + self.meanmotion as f64 * time;
+        if !self.params.is_open() {
+            mean = mean.rem_euclid(std::f64::consts::TAU);
+        }
+        self.params.trueanomaly = mean_to_true(mean as Float, self.params.eccentricity);
+    }
+
+    /// takes the current elements as holding `time` seconds after the scene epoch
+    pub fn anchor(&mut self, time: f64) {
+        let mean = true_to_mean(self.params.trueanomaly, self.params.eccentricity) as f64;
+        self.epochmean = mean - self.meanmotion as f64 * time;
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct OrbitalParams {
+    /// semimajor axis, or the periapsis distance for open orbits where it is not finite
     pub semimajor: Float,
     pub eccentricity: Float,
     pub inclination: Float,
@@ -172,6 +557,44 @@ impl OrbitalParams {
             semimajor, eccentricity, inclination, longitudeascnode, argofperiapsis, trueanomaly
         }
     }
+
+    pub fn is_open(&self) -> bool {
+        self.eccentricity >= 1.0
+    }
+
+    /// semi-latus rectum, the one size every conic has
+    pub fn semilatus(&self) -> Float {
+        if self.is_open() {
+            self.semimajor * (1.0 + self.eccentricity)
+        }
+        else {
+            self.semimajor * (1.0 - self.eccentricity * self.eccentricity)
+        }
+    }
+
+    /// mean motion about a body of the given gm, in the units of `semimajor`. parabolas use the
+    /// rate that goes with barker's equation
+    pub fn meanmotion(&self, gm: Float) -> Float {
+        if !self.is_open() {
+            return (gm / self.semimajor.powi(3)).sqrt();
+        }
+        if self.eccentricity - 1.0 < PARABOLIC {
+            return (gm / (2.0 * self.semimajor.powi(3))).sqrt();
+        }
+        let semiaxis = self.semimajor / (self.eccentricity - 1.0);
+        (gm / semiaxis.powi(3)).sqrt()
+    }
+
+    /// true anomalies worth drawing. ellipses close on themselves, open orbits are cut off
+    /// where they reach `reach` from the focus, which is always short of the asymptotes
+    pub fn anomaly_range(&self, reach: Float) -> (Float, Float) {
+        if !self.is_open() {
+            return (0.0, TAU);
+        }
+        let cos = ((self.semilatus() / reach - 1.0) / self.eccentricity).clamp(-1.0, 1.0);
+        let limit = cos.acos();
+        (-limit, limit)
+    }
 }
 
 #[derive(Debug)]
@@ -199,34 +622,337 @@ impl SpacialReference {
     }
 }
 
+/// the activity of a comet body. sizes are taken at 1 au from the sun and shrink to nothing
+/// out past the frost line
+#[derive(Debug)]
+pub struct Comet {
+    pub coma: Float,
+    pub tail: Float,
+}
+
+impl Comet {
+    pub fn cons(coma: Float, tail: Float) -> Comet {
+        Comet { coma, tail }
+    }
+
+    /// how active the comet is at a distance from the sun in scene units, falling off with the
+    /// sunlight it gets and fading out between 3 and 5 au
+    pub fn activity(distance: Float) -> Float {
+        let distance = distance * DISTANCESCALE / AU;
+        let fade = ((5.0 - distance) / 2.0).clamp(0.0, 1.0);
+        (fade * fade * (3.0 - 2.0 * fade) / (distance * distance).max(0.1)).min(10.0)
+    }
+}
+
+/// a field of small bodies on random orbits about the sun. kept apart from `Planet` as
+/// parallel arrays so thousands of them stay cheap to move and draw
+#[derive(Debug)]
+pub struct Belt {
+    pub moving: bool,
+    pub semilatus: Vec<Float>,
+    pub eccentricity: Vec<Float>,
+    pub meananomaly: Vec<Float>,
+    pub meanmotion: Vec<Float>,
+    /// unit vectors toward periapsis and a quarter turn ahead of it, in the scene frame
+    pub periapsis: Vec<Vec3>,
+    pub ahead: Vec<Vec3>,
+    /// where each member is, worked out once per step rather than once per frame drawn
+    positions: Vec<Vec3>,
+}
+
+impl Belt {
+    /// semimajor axes between inner and outer km, spread for an even surface density.
+    /// eccentricities stay small and inclinations scatter normally about the ecliptic
+    pub fn generate(inner: Float, outer: Float, count: usize, spread: Float, seed: u64) -> Belt {
+        let mut rng = Rng::seeded(seed);
+        let mut belt = Belt {
+            moving: true,
+            semilatus: Vec::with_capacity(count), eccentricity: Vec::with_capacity(count),
+            meananomaly: Vec::with_capacity(count), meanmotion: Vec::with_capacity(count),
+            periapsis: Vec::with_capacity(count), ahead: Vec::with_capacity(count),
+            positions: Vec::new(),
+        };
+        (0..count).for_each(|_| {
+            let (innersquare, outersquare) = (inner * inner, outer * outer);
+            let semimajor = (innersquare + rng.uniform() * (outersquare - innersquare)).sqrt();
+            let params = OrbitalParams::cons(
+                semimajor, 0.15 * rng.uniform(), spread * rng.gauss(), TAU * rng.uniform(),
+                TAU * rng.uniform(), 0.0,
+            );
+            belt.semilatus.push(params.semilatus());
+            belt.eccentricity.push(params.eccentricity);
+            belt.meananomaly.push(TAU * rng.uniform());
+            belt.meanmotion.push(params.meanmotion(SUNGM));
+            belt.periapsis.push(perifocal_to_frame(Vec3::cons(1, 0, 0), &params));
+            belt.ahead.push(perifocal_to_frame(Vec3::cons(0, 1, 0), &params));
+        });
+        belt.place();
+        belt
+    }
+
+    pub fn len(&self) -> usize {
+        self.semilatus.len()
+    }
+
+    pub fn advance(&mut self, seconds: Float) {
+        if !self.moving { return; }
+        self.meananomaly.iter_mut().zip(&self.meanmotion).for_each(|(mean, motion)| {
+            *mean = (*mean + motion * seconds).rem_euclid(TAU);
+        });
+        self.place();
+    }
+
+    pub fn scale(&mut self, factor: Float) {
+        self.semilatus.iter_mut().for_each(|semilatus| *semilatus /= factor);
+        self.place();
+    }
+
+    pub fn position(&self, idx: usize) -> Vec3 {
+        self.positions[idx]
+    }
+
+    fn place(&mut self) {
+        self.positions = (0..self.len()).map(|idx| {
+            let eccentricity = self.eccentricity[idx];
+            let trueanomaly = mean_to_true(self.meananomaly[idx], eccentricity);
+            let radius = self.semilatus[idx] / (1.0 + eccentricity * trueanomaly.cos());
+            let (sin, cos) = trueanomaly.sin_cos();
+            self.periapsis[idx] * (radius * cos) + self.ahead[idx] * (radius * sin)
+        }).collect();
+    }
+}
+
 #[derive(Debug)]
 pub struct System {
     pub planets: Vec<Planet>,
     pub lightsources: Vec<Vec3>,
+    pub physics: Option<Physics>,
+    pub belts: Vec<Belt>,
+    pub probe: Option<Probe>,
+    pub transfer: Option<Transfer>,
+    /// seconds since the scene epoch, the one simulation clock. f64 so single frames still
+    /// register years out at real time
+    pub elapsed: f64,
 }
 
 impl System {
     pub fn from(planet: Planet) -> System {
         let source = planet.loc;
-        System { planets: vec![planet], lightsources: vec![source] }
+        System {
+            planets: vec![planet], lightsources: vec![source], physics: None, belts: Vec::new(),
+            probe: None, transfer: None, elapsed: 0.0,
+        }
     }
 
     pub fn transform_mini(&mut self) {
         self.planets.iter_mut().for_each(|planet| {
-            planet.rad /= 500.0;
-            planet.loc /= 500000.0;
+            planet.rad /= RADIUSSCALE;
+            planet.loc /= DISTANCESCALE;
             if planet.name == "sun" {
                 planet.rad /= 20.0;
             }
             planet.features.iter_mut().for_each(|feature| {
                 match feature {
-                    Feature::Orbit(orbit) => orbit.params.semimajor /= 500000.0,
-                    Feature::Ring(ring) => { ring.rad /= 500.0; ring.depth /= 500.0; },
-                    Feature::SpacialReference(spaceref) => spaceref.length /= 500.0,
-                    Feature::Moon(moon) => moon.rad /= 500.0,
+                    Feature::Orbit(orbit) => orbit.params.semimajor /= DISTANCESCALE,
+                    Feature::Ring(ring) => { ring.rad /= RADIUSSCALE; ring.depth /= RADIUSSCALE; },
+                    Feature::SpacialReference(spaceref) => spaceref.length /= RADIUSSCALE,
+                    Feature::Moon(moon) => moon.rad /= RADIUSSCALE,
+                    Feature::Comet(comet) => {
+                        comet.coma /= RADIUSSCALE;
+                        comet.tail /= DISTANCESCALE;
+                    }
+                    Feature::Trajectory(trajectory) => trajectory.scale(DISTANCESCALE),
                 }
             });
         });
+        self.belts.iter_mut().for_each(|belt| belt.scale(DISTANCESCALE));
+        self.place_trajectories();
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Planet> {
+        self.planets.iter().find(|planet| planet.name == name)
+    }
+
+    /// moves every body along its path by `seconds` of simulation time, or by the integrator in
+    /// n-body mode. moons have no path of their own at this scale, so they are carried along
+    /// with their parent either way
+    pub fn advance(&mut self, seconds: Float) {
+        if seconds == 0.0 { return; }
+        self.elapsed += seconds as f64;
+        // the probe flies from where everything was at the start of the step
+        let attractors = self.probe.as_ref().map(|_| Attractor::gather(&self.planets));
+        // paths keep running in n-body mode so switching back lands on the same date
+        self.planets.iter_mut().for_each(|planet| {
+            if let Some(orbit) = planet.path_mut() {
+                orbit.at(self.elapsed);
+            }
+        });
+        if let Some(physics) = &mut self.physics {
+            physics.advance(seconds as f64);
+        }
+        self.belts.iter_mut().for_each(|belt| belt.advance(seconds));
+        if let (Some(probe), Some(attractors)) = (&mut self.probe, attractors) {
+            probe.advance(seconds as f64, &attractors);
+            probe.refresh(&Attractor::gather(&self.planets));
+        }
+        self.settle();
+    }
+
+    /// puts every body where its path or the integrator says it is right now
+    fn settle(&mut self) {
+        let mut moved: Vec<(String, Vec3)> = Vec::new();
+        let locs: Vec<(String, Vec3)> = match &self.physics {
+            Some(physics) => {
+                physics.positions().map(|(name, loc)| (name.to_owned(), loc)).collect()
+            }
+            None => self.planets.iter().filter_map(|planet| {
+                Some((planet.name.clone(), orbital_cartesian_transformation(planet.path()?)))
+            }).collect(),
+        };
+        locs.into_iter().for_each(|(name, loc)| {
+            if let Some(planet) = self.planets.iter_mut().find(|planet| planet.name == name) {
+                moved.push((name, loc - planet.loc));
+                planet.loc = loc;
+            }
+        });
+        self.planets.iter_mut().for_each(|planet| {
+            let parent = planet.parent.as_ref()
+                .and_then(|parent| moved.iter().find(|(name, _)| name == parent));
+            if let Some((_, delta)) = parent {
+                planet.loc += *delta;
+            }
+        });
+        self.place_trajectories();
+        self.place_probe();
+        self.lightsources = self.planets.iter()
+            .filter(|planet| planet.lightsource)
+            .map(|planet| planet.loc)
+            .collect();
+    }
+
+    /// puts every trajectory marker where its recording has it now, offset by wherever its
+    /// center body currently is
+    fn place_trajectories(&mut self) {
+        let markers = self.planets.iter().enumerate();
+        let placed: Vec<(usize, Vec3)> = markers.filter_map(|(idx, planet)| {
+            let trajectory = planet.trajectory()?;
+            Some((idx, self.trajectory_origin(trajectory) + trajectory.position(self.elapsed)))
+        }).collect();
+        placed.into_iter().for_each(|(idx, loc)| self.planets[idx].loc = loc);
+    }
+
+    fn place_probe(&mut self) {
+        let Some(loc) = self.probe.as_ref().map(|probe| probe.loc()) else { return; };
+        if let Some(marker) = self.planets.iter_mut().find(|planet| planet.name == PROBENAME) {
+            marker.loc = loc;
+        }
+    }
+
+    /// starts a probe at a point in scene units on a circular orbit about the sun, plus
+    /// `launch` in km/s. any probe already flying is replaced
+    pub fn spawn_probe(&mut self, loc: Vec3, launch: Vec3) {
+        let relative = loc - self.find("sun").map_or(Vec3::cons(0, 0, 0), |sun| sun.loc);
+        // prograde in the ecliptic, which the mirrored x turns clockwise seen from above
+        let mut prograde = Vec3::cons(relative.y, -relative.x, 0.0);
+        if prograde.length() > 0.0 {
+            prograde.normalize();
+            prograde *= (SUNGM / (relative.length() * DISTANCESCALE)).sqrt();
+        }
+        let attractors = Attractor::gather(&self.planets);
+        self.probe = Some(Probe::spawn(loc, prograde + launch, &attractors));
+        if self.find(PROBENAME).is_none() {
+            let mut marker = Planet::cons(PROBENAME.to_owned(), loc, MARKERRADIUS / RADIUSSCALE,
+                None, false, None);
+            marker.physrad = MARKERRADIUS;
+            self.add_planet(marker);
+        }
+        self.place_probe();
+    }
+
+    pub fn burn_probe(&mut self, burn: Burn, deltav: f64) {
+        let attractors = Attractor::gather(&self.planets);
+        if let Some(probe) = &mut self.probe {
+            probe.burn(burn, deltav, &attractors);
+        }
+    }
+
+    /// the first pick is where a transfer leaves from and the second where it goes. picking
+    /// again starts a new plan, and picking the departure body twice drops it
+    pub fn pick_transfer(&mut self, name: &str) {
+        match self.transfer.take() {
+            Some(transfer) if transfer.to.is_none() && transfer.from == name => {}
+            Some(mut transfer) if transfer.to.is_none() => {
+                transfer.to = Some(name.to_owned());
+                self.transfer = Some(transfer);
+            }
+            _ => self.transfer = Some(Transfer::cons(name)),
+        }
+    }
+
+    /// solves the picked transfer for leaving on a julian day, or now without one. the flight
+    /// takes `days`, or as long as the hohmann transfer when that is zero
+    pub fn plan_lambert(&mut self, date: Option<f64>, days: f64) {
+        let Some(mut transfer) = self.transfer.take() else { return; };
+        let date = date.map_or(self.elapsed, |date| {
+            (date - julian_day(SCENEEPOCH).unwrap()) * 86400.0
+        });
+        let flight = if days > 0.0 {
+            Some(days * 86400.0)
+        }
+        else {
+            transfer.hohmann(self).map(|hohmann| hohmann.flight)
+        };
+        if let Some(flight) = flight {
+            transfer.plan_lambert(self, date, flight);
+        }
+        self.transfer = Some(transfer);
+    }
+
+    /// where a trajectory's coordinates are measured from, the sun unless it names a center
+    pub fn trajectory_origin(&self, trajectory: &Trajectory) -> Vec3 {
+        trajectory.center.as_ref().and_then(|center| self.find(center))
+            .map_or(Vec3::cons(0, 0, 0), |center| center.loc)
+    }
+
+    /// swaps a body's own path for a new one, moving the body and its moons onto it
+    pub fn set_path(&mut self, name: &str, mut path: Orbit) {
+        path.anchor(self.elapsed);
+        let Some(planet) = self.planets.iter_mut().find(|planet| planet.name == name) else {
+            return;
+        };
+        let Some(orbit) = planet.path_mut() else { return; };
+        *orbit = path;
+        let loc = orbital_cartesian_transformation(&path);
+        let delta = loc - planet.loc;
+        planet.loc = loc;
+        self.planets.iter_mut()
+            .filter(|planet| planet.parent.as_deref() == Some(name))
+            .for_each(|moon| moon.loc += delta);
+        self.lightsources = self.planets.iter()
+            .filter(|planet| planet.lightsource)
+            .map(|planet| planet.loc)
+            .collect();
+    }
+
+    /// switches between keplerian paths and n-body integration. integration starts from the
+    /// current elements, and bodies that only it moves are put back when it stops
+    pub fn toggle_physics(&mut self) {
+        match self.physics.take() {
+            Some(physics) => {
+                physics.starts().for_each(|(name, start)| {
+                    let planet = self.planets.iter_mut().find(|planet| planet.name == name);
+                    if let Some(planet) = planet.filter(|planet| planet.path().is_none()) {
+                        planet.loc = start;
+                    }
+                });
+                self.settle();
+            }
+            None => self.physics = Some(Physics::from(&self.planets, SUNGM as f64)),
+        }
+    }
+
+    pub fn add_belt(&mut self, belt: Belt) {
+        self.belts.push(belt);
     }
 
     pub fn add_planet(&mut self, planet: Planet) {
@@ -242,6 +968,20 @@ impl System {
             Feature::Ring(ring) => self.add_ring(target, ring),
             Feature::Orbit(orbit) => self.add_orbit(target, orbit),
             Feature::Moon(moon) => self.add_moon(target, moon),
+            Feature::Comet(comet) => self.add_comet(target, comet),
+            Feature::Trajectory(trajectory) => self.add_trajectory(target, trajectory),
+        }
+    }
+
+    pub fn add_trajectory(&mut self, target: &str, trajectory: Trajectory) {
+        if let Some(planet) = self.planets.iter_mut().find(|planet| planet.name == target) {
+            planet.features.push(Feature::Trajectory(trajectory));
+        }
+    }
+
+    pub fn add_comet(&mut self, target: &str, comet: Comet) {
+        if let Some(planet) = self.planets.iter_mut().find(|planet| planet.name == target) {
+            planet.features.push(Feature::Comet(comet));
         }
     }
 
@@ -266,9 +1006,73 @@ impl System {
     pub fn add_moon(&mut self, target: &str, moon: Planet) {
         if let Some(planet) = self.planets.iter().find(|planet| planet.name == target) {
             let mut moon = moon;
-            moon.loc *= 500.0;
+            moon.loc *= RADIUSSCALE;
             moon.loc += planet.loc;
+            moon.parent = Some(planet.name.clone());
             self.add_planet(moon);
         }
     }
 }
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn activity(au: Float) -> Float {
+        Comet::activity(au * AU / DISTANCESCALE)
+    }
+
+    #[test]
+    fn clock_holds_late() {
+        let mut system = System::from(Planet::cons("sun".to_owned(), Vec3::cons(0, 0, 0), 1.0,
+            None, true, None));
+        system.advance(3.15e7);
+        system.advance(1.0 / 60.0);
+        assert!(system.elapsed > 3.15e7, "a frame a year in was lost");
+    }
+
+    #[test]
+    fn roll_needs_freeflight() {
+        let mut viewmodel = ViewModel::new(Vec3::cons(0, 0, 0));
+        viewmodel.roll(1.0);
+        assert!(viewmodel.bank().abs() < 1e-6, "rolled level view {}", viewmodel.bank());
+        viewmodel.toggle_freeflight();
+        viewmodel.roll(1.0);
+        assert!(viewmodel.bank().abs() > 1e-3, "free flight did not roll");
+    }
+
+    #[test]
+    fn paths_follow_clock() {
+        // a circular path at 1 au, a year in and then a minute of frames at real time
+        let mut system = System::from(Planet::cons("sun".to_owned(), Vec3::cons(0, 0, 0), 1.0,
+            None, true, None));
+        system.add_planet(Planet::cons("earth".to_owned(), Vec3::cons(0, 0, 0), 1.0,
+            None, false, None));
+        let params = OrbitalParams::cons(1.496e8, 0.0, 0.0, 0.0, 0.0, 1.0);
+        system.add_orbit("earth", Orbit::cons(params, Vec3::cons(0, 0, 0), false));
+        system.advance(3.15e7);
+        (0..3600).for_each(|_| system.advance(1.0 / 60.0));
+
+        let motion = (SUNGM as f64 / 1.496e8f64.powi(3)).sqrt();
+        let expected = (1.0 + motion * system.elapsed).rem_euclid(std::f64::consts::TAU);
+        let anomaly = system.find("earth").unwrap().path().unwrap().params.trueanomaly as f64;
+        assert!((anomaly - expected).abs() < 2e-6, "anomaly {} not {}", anomaly, expected);
+    }
+
+    #[test]
+    fn comet_activity() {
+        // sunlight falls off as 1/d^2 inside the frost line
+        assert!((activity(1.0) - 1.0).abs() < 1e-4, "1 au {}", activity(1.0));
+        assert!((activity(2.0) - 0.25).abs() < 1e-4, "2 au {}", activity(2.0));
+        assert!((activity(3.0) - 1.0 / 9.0).abs() < 1e-4, "3 au {}", activity(3.0));
+        // smoothstep fade from 3 to 5 au, halfway at 4
+        assert!((activity(4.0) - 0.5 / 16.0).abs() < 1e-4, "4 au {}", activity(4.0));
+        assert!(activity(4.5) < activity(3.5) && activity(4.5) > 0.0);
+        assert!(activity(5.0) == 0.0 && activity(30.0) == 0.0);
+        // close in it is capped rather than blowing up
+        assert!(activity(0.2) == 10.0 && activity(0.01) == 10.0);
+        assert!(activity(0.5) < 10.0);
+    }
+}
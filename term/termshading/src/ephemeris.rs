@@ -0,0 +1,88 @@
+use crate::entities::{Orbit, OrbitalParams};
+use crate::math::{mean_to_true, Vec3};
+use crate::{Float, AU, DISTANCESCALE};
+
+
+
+/// julian day of j2000, where the table and its rates are measured from
+const J2000: f64 = 2451545.0;
+/// span the fit holds to, outside it positions drift by more than the table's stated error
+pub const VALIDFROM: f64 = 2378496.5;
+pub const VALIDTO: f64 = 2470172.5;
+
+/// keplerian elements of the planets at j2000 and their rates per julian century, from jpl's
+/// "approximate positions of the planets" (standish, table 1, 1800 ad to 2050 ad). columns are
+/// a (au), e, i, mean longitude, longitude of perihelion and longitude of the ascending node
+/// (degrees). the earth row is the earth-moon barycenter
+const ELEMENTS: [(&str, [f64; 6], [f64; 6]); 9] = [
+    ("mercury",
+        [0.38709927, 0.20563593, 7.00497902, 252.25032350, 77.45779628, 48.33076593],
+        [0.00000037, 0.00001906, -0.00594749, 149472.67411175, 0.16047689, -0.12534081]),
+    ("venus",
+        [0.72333566, 0.00677672, 3.39467605, 181.97909950, 131.60246718, 76.67984255],
+        [0.00000390, -0.00004107, -0.00078890, 58517.81538729, 0.00268329, -0.27769418]),
+    ("earth",
+        [1.00000261, 0.01671123, -0.00001531, 100.46457166, 102.93768193, 0.0],
+        [0.00000562, -0.00004392, -0.01294668, 35999.37244981, 0.32327364, 0.0]),
+    ("mars",
+        [1.52371034, 0.09339410, 1.84969142, -4.55343205, -23.94362959, 49.55953891],
+        [0.00001847, 0.00007882, -0.00813131, 19140.30268499, 0.44441088, -0.29257343]),
+    ("jupiter",
+        [5.20288700, 0.04838624, 1.30439695, 34.39644051, 14.72847983, 100.47390909],
+        [-0.00011607, -0.00013253, -0.00183714, 3034.74612775, 0.21252668, 0.20469106]),
+    ("saturn",
+        [9.53667594, 0.05386179, 2.48599187, 49.95424423, 92.59887831, 113.66242448],
+        [-0.00125060, -0.00050991, 0.00193609, 1222.49362201, -0.41897216, -0.28867794]),
+    ("uranus",
+        [19.18916464, 0.04725744, 0.77263783, 313.23810451, 170.95427630, 74.01692503],
+        [-0.00196176, -0.00004397, -0.00242939, 428.48202785, 0.40805281, 0.04240589]),
+    ("neptune",
+        [30.06992276, 0.00859048, 1.77004347, -55.12002969, 44.96476227, 131.78422574],
+        [0.00026291, 0.00005105, 0.00035372, 218.45945325, -0.32241464, -0.00508664]),
+    ("pluto",
+        [39.48211675, 0.24882730, 17.14001206, 238.92903833, 224.06891629, 110.30393684],
+        [-0.00031596, 0.00005170, 0.00004818, 145.20780515, -0.04062942, -0.01183482]),
+];
+
+
+
+/// elements for a planet on the given julian day, with a in km and angles in radians
+pub fn elements(name: &str, julianday: f64) -> Option<OrbitalParams> {
+    let (_, base, rates) = ELEMENTS.iter().find(|(planet, _, _)| *planet == name)?;
+    let centuries = (julianday - J2000) / 36525.0;
+    let [semimajor, eccentricity, inclination, longitude, perihelion, node]: [f64; 6] =
+        std::array::from_fn(|idx| base[idx] + rates[idx] * centuries);
+
+    // mean anomaly wrapped in f64, a few centuries of mean longitude is a lot of turns
+    let mean = (longitude - perihelion).rem_euclid(360.0).to_radians() as Float;
+    Some(OrbitalParams::cons(
+        (semimajor * AU as f64) as Float, eccentricity as Float,
+        inclination.to_radians() as Float, node.to_radians() as Float,
+        (perihelion - node).to_radians() as Float, mean_to_true(mean, eccentricity as Float),
+    ))
+}
+
+/// the planet's own path for that day in scene units, ready to replace the snapshot's
+pub fn path(name: &str, julianday: f64) -> Option<Orbit> {
+    let mut orbit = Orbit::cons(elements(name, julianday)?, Vec3::cons(0, 0, 0), false);
+    orbit.params.semimajor /= DISTANCESCALE;
+    Some(orbit)
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::math::orbital_cartesian_transformation;
+
+    #[test]
+    fn earth_at_j2000() {
+        let orbit = Orbit::cons(elements("earth", J2000).unwrap(), Vec3::cons(0, 0, 0), false);
+        let pos = orbital_cartesian_transformation(&orbit);
+        // the scene frame is mirrored in x
+        let longitude = pos.y.atan2(-pos.x).to_degrees();
+        assert!((longitude - 100.38).abs() < 0.1, "longitude {}", longitude);
+        assert!((pos.length() / AU - 0.9833).abs() < 1e-3, "distance {}", pos.length() / AU);
+    }
+}
@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::fs::{write, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use image::{ImageFormat, RgbImage};
+
+use crate::renderer::Buffer;
+use crate::{Float, Int};
+
+
+
+/// grey used for cells that only hold a glyph, like unlit orbit dots or labels
+const GLYPHSHADE: u8 = 200;
+
+
+
+/// writes the buffer out in the format picked by the file extension: png, ppm or ans. for the
+/// images each cell becomes a block `scale` pixels wide and `scale * aspect` pixels tall, with
+/// the text overlay only drawn in when `overlay` is set
+pub fn save_frame(
+    buffer: &Buffer, path: &str, scale: Int, aspect: Float, overlay: bool
+) -> Result<(), Box<dyn Error>> {
+    let extension = Path::new(path).extension().and_then(|ext| ext.to_str());
+    match extension {
+        Some("png") => {
+            let (width, height, pixels) = rasterize(buffer, scale, aspect, overlay);
+            let image = RgbImage::from_raw(width, height, pixels).ok_or("bad image size")?;
+            image.save_with_format(path, ImageFormat::Png)?;
+        }
+        Some("ppm") => {
+            let (width, height, pixels) = rasterize(buffer, scale, aspect, overlay);
+            write_ppm(path, width, height, &pixels)?;
+        }
+        Some("ans") => {
+            write(path, buffer.to_ansi())?;
+        }
+        _ => return Err(format!("unsupported output {}, expected .png, .ppm or .ans", path).into()),
+    }
+    Ok(())
+}
+
+/// whether a path is written as an image rather than as text
+pub fn is_image(path: &str) -> bool {
+    matches!(Path::new(path).extension().and_then(|ext| ext.to_str()), Some("png" | "ppm"))
+}
+
+/// flattens the buffer into packed rgb. column zero is skipped since display never shows it
+pub fn rasterize(buffer: &Buffer, scale: Int, aspect: Float, overlay: bool) -> (u32, u32, Vec<u8>) {
+    let cellwidth = scale.max(1) as usize;
+    let cellheight = (scale.max(1) as Float * aspect).round().max(1.0) as usize;
+    let (columns, rows) = (buffer.width as usize - 1, buffer.height as usize);
+    let (width, height) = (columns * cellwidth, rows * cellheight);
+    let mut pixels = vec![0; width * height * 3];
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let idx = row * buffer.width as usize + column + 1;
+            let (glyph, color) = if overlay { buffer.cell(idx) } else { buffer.scene_cell(idx) };
+            let rgb = match color {
+                Some(color) => [color.red, color.green, color.blue],
+                None if glyph != ' ' => [GLYPHSHADE; 3],
+                None => [0; 3],
+            };
+            for y in row * cellheight..(row + 1) * cellheight {
+                for x in column * cellwidth..(column + 1) * cellwidth {
+                    let idx = (y * width + x) * 3;
+                    pixels[idx..idx + 3].copy_from_slice(&rgb);
+                }
+            }
+        }
+    }
+    (width as u32, height as u32, pixels)
+}
+
+pub fn write_ppm(path: &str, width: u32, height: u32, pixels: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "P6\n{} {}\n255\n", width, height)?;
+    file.write_all(pixels)?;
+    Ok(())
+}
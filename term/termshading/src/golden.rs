@@ -0,0 +1,148 @@
+use std::env;
+use std::error::Error;
+use std::fs::{create_dir_all, read};
+
+use crate::configparser::{parse_system, Config, SUNPATH};
+use crate::entities::{Planet, System, ViewModel};
+use crate::export::{rasterize, write_ppm};
+use crate::math::Vec3;
+use crate::profiler::Profiler;
+use crate::renderer::{Buffer, Renderer};
+use crate::SUNGM;
+
+
+
+/// reference frames live here, one pixel per cell. run with GOLDEN_BLESS=1 to rewrite them
+/// after an intended change to shading or projection
+const GOLDENDIR: &str = "golden";
+const DIFFDIR: &str = "target/golden";
+/// channel difference a cell may drift by before it counts as changed
+const CHANNELTOLERANCE: u8 = 24;
+/// share of changed cells a frame may have before the test fails
+const CELLTOLERANCE: f32 = 0.01;
+/// the scenes are built from these lines rather than systemconfig.config, so editing the
+/// config does not move the frames. belts, comets and trajectories are left out on purpose
+const FIXTURE: &str = "
+planet mercury 2440 orbital=5.79E+7,2.05E-1,7.003,4.829E+1,2.919E+1,1.55E+2 gm=22031.87
+planet venus 6051 orbital=1.08E+8,6.7E-3,3.39,7.66E+1,5.512E+1,2.95E+2 gm=324858.59
+planet earth 6378 orbital=1.494E+8,1.598E-2,3.16E-3,1.86E+2,2.77E+2,7.17 params=23,270 gm=403503.24
+planet mars 3396 orbital=2.279E+8,0.34E-2,1.85,4.94E+1,2.86E+2,1.37E+2 gm=42828.37
+planet jupiter 71492 orbital=7.78E+8,4.8E-2,1.303,1.005E+2,2.733E+2,6.5E+1 gm=126712764.1
+planet saturn 60268 orbital=1.429E+9,5.5E-2,2.488,1.137E+2,3.367E+2,2.59E+2 gm=37940584.8
+planet uranus 25559 orbital=2.887E+9,4.567E-2,7.72E-1,7.4E+1,9.05E+1,2.509E+2 gm=5794556.4
+planet neptune 24766 orbital=4.5E+9,1.29E-2,1.76,1.31E+2,2.67E+2,3.19E+2 gm=6836527.1
+planet pluto 1188 orbital=5.89E+9,2.46E-1,1.71E+1,1.1E+2,1.13E+2,7.79E+1 gm=977.0
+moon luna 1137 orbital=3.84E+5,3.63E-2,5.03,3.59E+2,3.7E+1,3.57E+1 target=earth gm=4902.8
+ring saturn dimens=75000,50000
+ring uranus dimens=70000,3000 params=97,0
+spaceref earth 50000
+";
+
+
+
+fn fixture_system() -> System {
+    let mut sun = Planet::cons("sun".to_owned(), Vec3::cons(0, 0, 0), 695700.0,
+        Some(SUNPATH), true, None);
+    sun.gm = Some(SUNGM as f64);
+    let mut system = System::from(sun);
+    parse_system(FIXTURE.as_bytes(), &mut system).unwrap();
+    system.transform_mini();
+    system
+}
+
+fn scene_config() -> Config {
+    let mut config = Config::default();
+    config.set_size(120, 40);
+    config.set_fov(90.0);
+    config.toggle_panel();
+    config
+}
+
+fn render(viewmodel: &ViewModel, system: &System, config: &Config) -> (u32, u32, Vec<u8>) {
+    let mut buffer = Buffer::cons(config.height(), config.width());
+    buffer.reserve(config.panel_reserve());
+    Renderer::cons(viewmodel, &mut buffer, system, config).render_frame(&mut Profiler::new());
+    rasterize(&buffer, 1, 1.0, true)
+}
+
+fn read_ppm(path: &str) -> Result<(u32, u32, Vec<u8>), Box<dyn Error>> {
+    let data = read(path)?;
+    let mut fields = Vec::new();
+    let mut start = 0;
+    // header is magic, width, height and max value separated by single whitespace
+    for (idx, byte) in data.iter().enumerate() {
+        if byte.is_ascii_whitespace() {
+            fields.push(String::from_utf8(data[start..idx].to_vec())?);
+            start = idx + 1;
+            if fields.len() == 4 { break; }
+        }
+    }
+    if fields.len() != 4 || fields[0] != "P6" {
+        return Err(format!("{} is not a binary ppm", path).into());
+    }
+    Ok((fields[1].parse()?, fields[2].parse()?, data[start..].to_vec()))
+}
+
+fn check_frame(name: &str, frame: (u32, u32, Vec<u8>)) {
+    let reference = format!("{}/{}.ppm", GOLDENDIR, name);
+    let (width, height, pixels) = frame;
+    if env::var("GOLDEN_BLESS").is_ok() {
+        write_ppm(&reference, width, height, &pixels).unwrap();
+        return;
+    }
+
+    let (refwidth, refheight, refpixels) = read_ppm(&reference).unwrap_or_else(|err| {
+        panic!("missing reference {}, bless it with GOLDEN_BLESS=1: {}", reference, err)
+    });
+    assert!((refwidth, refheight) == (width, height), "{} changed size", name);
+
+    let mut diff = vec![0; pixels.len()];
+    let mut changed = 0;
+    pixels.chunks(3).zip(refpixels.chunks(3)).enumerate().for_each(|(idx, (cell, refcell))| {
+        let drift = cell.iter().zip(refcell).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0);
+        if drift > CHANNELTOLERANCE {
+            changed += 1;
+            diff[idx * 3] = 255;
+        }
+        else {
+            // keep a dim copy of the frame so the changes can be placed
+            diff[idx * 3 + 1] = cell[1] / 3;
+            diff[idx * 3 + 2] = cell[2] / 3;
+        }
+    });
+
+    let share = changed as f32 / (width * height) as f32;
+    if share > CELLTOLERANCE {
+        create_dir_all(DIFFDIR).unwrap();
+        let actual = format!("{}/{}.ppm", DIFFDIR, name);
+        let diffpath = format!("{}/{}.diff.ppm", DIFFDIR, name);
+        write_ppm(&actual, width, height, &pixels).unwrap();
+        write_ppm(&diffpath, width, height, &diff).unwrap();
+        panic!("{} drifted in {:.2}% of cells, see {} and {}",
+            name, share * 100.0, actual, diffpath);
+    }
+}
+
+#[test]
+fn earth_default() {
+    let system = fixture_system();
+    let config = scene_config();
+    let mut viewmodel = ViewModel::new(Vec3::cons(0, 0, 0));
+    viewmodel.goto("earth", &system);
+    viewmodel.land(&system);
+    check_frame("earth_default", render(&viewmodel, &system, &config));
+}
+
+#[test]
+fn saturn_rings() {
+    let system = fixture_system();
+    let config = scene_config();
+    let saturn = system.find("saturn").unwrap();
+    // sunward of saturn and a little above the ring plane, so the rings are lit and open
+    let mut toward = saturn.loc * -1.0;
+    toward.normalize();
+    let above = Vec3::cons(0.0, 0.0, saturn.rad * 2.0);
+    let mut viewmodel = ViewModel::new(saturn.loc + toward * (saturn.rad * 6.0) + above);
+    viewmodel.look_at(saturn.loc);
+    check_frame("saturn_rings", render(&viewmodel, &system, &config));
+}
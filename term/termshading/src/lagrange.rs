@@ -0,0 +1,106 @@
+use crate::entities::{Planet, System};
+use crate::math::{orbital_velocity, Vec3};
+use crate::Float;
+
+
+
+/// newton steps for the collinear points, which start from close guesses
+const COLLINEARSTEPS: usize = 30;
+/// bodies are drawn far bigger than their orbits, so collinear points closer to the smaller
+/// body than this many of its drawn radii are set out to that distance
+const CLEARANCE: Float = 1.5;
+
+
+
+#[derive(Debug)]
+pub struct LagrangePoint {
+    pub label: String,
+    pub loc: Vec3,
+}
+
+/// l1 to l5 for every body on a path about the sun and every moon about its planet, where both
+/// have a mass. worked from where the bodies are drawn, so they follow them and the stretched
+/// distances of moons
+pub fn lagrange_points(system: &System) -> Vec<LagrangePoint> {
+    system.planets.iter().filter_map(|secondary| {
+        let primary = match &secondary.parent {
+            Some(parent) => system.find(parent)?,
+            None => secondary.path().and_then(|_| system.find("sun"))?,
+        };
+        pair(primary, secondary)
+    }).flatten().collect()
+}
+
+fn pair(primary: &Planet, secondary: &Planet) -> Option<Vec<LagrangePoint>> {
+    let (big, small) = (primary.gm?, secondary.gm?);
+    let ratio = small / (big + small);
+    let offset = secondary.loc - primary.loc;
+    let distance = offset.length();
+    if distance == 0.0 { return None; }
+    let axis = offset / distance;
+    // moons have no path at this scale and are taken to go prograde in the ecliptic, which the
+    // mirrored x turns clockwise seen from above
+    let motion = match secondary.path() {
+        Some(path) => orbital_velocity(&path.params, 1.0),
+        None => Vec3::cons(axis.y, -axis.x, 0.0),
+    };
+    let mut ahead = motion - axis * motion.inner_prod(&axis);
+    if ahead.length() == 0.0 { return None; }
+    ahead.normalize();
+
+    let barycenter = primary.loc + offset * ratio as Float;
+    let hill = (ratio / 3.0).cbrt();
+    let guesses = [1.0 - ratio - hill, 1.0 - ratio + hill, -1.0 - 5.0 * ratio / 12.0];
+    let mut points: Vec<Vec3> = guesses.iter().map(|guess| {
+        barycenter + axis * (collinear(ratio, *guess) as Float * distance)
+    }).collect();
+    points.iter_mut().take(2).for_each(|point| {
+        let clearance = secondary.rad * CLEARANCE;
+        let side = (*point - secondary.loc).inner_prod(&axis).signum();
+        if (*point - secondary.loc).length() < clearance {
+            *point = secondary.loc + axis * (side * clearance);
+        }
+    });
+    let along = axis * (distance / 2.0);
+    let across = ahead * (distance * (3.0 as Float).sqrt() / 2.0);
+    points.push(primary.loc + along + across);
+    points.push(primary.loc + along - across);
+
+    Some(points.into_iter().enumerate().map(|(idx, loc)| {
+        LagrangePoint { label: format!("{} L{}", secondary.name, idx + 1), loc }
+    }).collect())
+}
+
+/// where along the line through both bodies the pulls and the turning frame balance, measured
+/// from the barycenter in units of their separation with the smaller body at 1 - ratio
+fn collinear(ratio: f64, guess: f64) -> f64 {
+    let (one, two) = (-ratio, 1.0 - ratio);
+    (0..COLLINEARSTEPS).fold(guess, |x, _| {
+        let (near, far) = (x - one, x - two);
+        let (nearcube, farcube) = (near.abs().powi(3), far.abs().powi(3));
+        let force = x - (1.0 - ratio) * near / nearcube - ratio * far / farcube;
+        let slope = 1.0 + 2.0 * (1.0 - ratio) / nearcube + 2.0 * ratio / farcube;
+        x - force / slope
+    })
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn earth_moon_collinear() {
+        // the earth moon points sit at 0.8369, 1.1557 and -1.0051 of the separation from the
+        // barycenter
+        let ratio: f64 = 0.01215;
+        let hill = (ratio / 3.0).cbrt();
+        let one = collinear(ratio, 1.0 - ratio - hill);
+        let two = collinear(ratio, 1.0 - ratio + hill);
+        let three = collinear(ratio, -1.0 - 5.0 * ratio / 12.0);
+        assert!((one - 0.8369).abs() < 1e-3, "l1 {}", one);
+        assert!((two - 1.1557).abs() < 1e-3, "l2 {}", two);
+        assert!((three + 1.0051).abs() < 1e-3, "l3 {}", three);
+    }
+}
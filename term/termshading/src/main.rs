@@ -7,14 +7,42 @@ mod utils;
 mod renderer;
 mod entities;
 mod configparser;
+mod controls;
+mod cli;
+mod export;
+mod recorder;
+mod replay;
+mod profiler;
+mod pacer;
+mod physics;
+mod ephemeris;
+mod trajectory;
+mod probe;
+mod transfer;
+mod lagrange;
+#[cfg(test)]
+mod golden;
 
 
 
+use std::io::{stdout, Write};
+use std::time::Instant;
+
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::execute;
+
+use crate::cli::{Args, Mode};
 use crate::configparser::{general_config, parse_config, Config, SUNPATH};
+use crate::controls::Action;
 use crate::renderer::{Buffer, Renderer};
 use crate::math::Vec3;
-use crate::entities::{Planet, System, ViewModel};
-use crate::utils::{dump, get_user_input, print_debug, sleep};
+use crate::entities::{Clock, Planet, System, ViewModel};
+use crate::export::{is_image, save_frame};
+use crate::recorder::Recorder;
+use crate::replay::Replay;
+use crate::profiler::Profiler;
+use crate::pacer::Pacer;
+use crate::utils::{debug_line, dump, flash_error, get_user_input, julian_day, print_debug};
 
 
 
@@ -22,7 +50,12 @@ const TAU: Float = 6.2831855;
 const PI: Float = 3.1415925;
 const SYSTEMCONFIG: &str = "systemconfig.config";
 const CONFIG: &str = "config.config";
+const SCENEEPOCH: &str = "2025-01-01";
 const FRAMEDELAY: u64 = 1000 / 60;
+const DISTANCESCALE: Float = 500000.0;
+const RADIUSSCALE: Float = 500.0;
+const AU: Float = 149597870.7;
+const SUNGM: Float = 1.3271244e11;
 
 
 
@@ -30,51 +63,165 @@ type Float = f32;
 type Int = i32;
 
 fn main() {
-    let mut config: Config = general_config(CONFIG).unwrap_or_else(|err| {
-        println!("error parsing config: {}", err);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = Args::parse(&args).unwrap_or_else(|err| {
+        println!("error parsing arguments: {}", err);
         panic!();
     });
-
+    let mut config = load_config(&args);
     let mut buffer = Buffer::cons(config.height(), config.width());
-    let sun = Planet::cons("sun".to_owned(), Vec3::cons(0, 0, 0), 695700.0,
-        Some(SUNPATH), true, None);
-    let mut system = System::from(sun);
-    let mut viewmodel = ViewModel::new(Vec3::cons(0, 0, 0));
-
-    parse_config(SYSTEMCONFIG, &mut system).unwrap_or_else(|err| {
-        println!("error parsing config: {}", err);
-        panic!();
+    let mut samples = config.supersample().map(|samples| {
+        Buffer::supersampled(config.height(), config.width(), samples)
     });
-    system.transform_mini();
-    viewmodel.goto("earth", &system);
-    
+    let (mut system, mut viewmodel, mut clock) = start_scene(&args, &config);
+
+    if let Mode::Bench(frames) = args.mode {
+        let mut profiler = Profiler::new();
+        (0..frames).for_each(|_| {
+            system.advance(clock.tick(FRAMEDELAY as Float / 1000.0));
+            viewmodel.update(FRAMEDELAY as Float / 1000.0, &system);
+            buffer.reserve(config.panel_reserve());
+            let start = Instant::now();
+            let mut renderer = Renderer::cons(&viewmodel, &mut buffer, &system, &config);
+            renderer.samples = samples.as_mut();
+            renderer.render_frame(&mut profiler);
+            // headless, so display is timed up to the string it would print
+            profiler.measure("display", || buffer.to_ansi());
+            profiler.record("frame", start.elapsed());
+        });
+        println!("{} frames at {}x{}", frames, config.width(), config.height());
+        print!("{}", profiler.report());
+        return;
+    }
+
+    if let Mode::Render = args.mode {
+        let out = args.out.as_deref().unwrap_or_default();
+        // text only comes out as grey blocks in an image, so images go without the panel
+        let overlay = args.overlay || !is_image(out);
+        if !overlay && config.render_panel() {
+            config.toggle_panel();
+        }
+        buffer.reserve(config.panel_reserve());
+        let mut renderer = Renderer::cons(&viewmodel, &mut buffer, &system, &config);
+        renderer.samples = samples.as_mut();
+        renderer.render_frame(&mut Profiler::new());
+        let aspect = config.termcharaspect();
+        save_frame(&buffer, out, args.scale, aspect, overlay).unwrap_or_else(|err| {
+            println!("error writing frame: {}", err);
+            panic!();
+        });
+        return;
+    }
+
     // ansi escape to clear terminal
     print!("\x1b[2J");
     // ansi escape to make cursor-line invisible for program
     print!("\x1b[?25l");
+    // scroll wheel drives the orbit camera radius
+    execute!(stdout(), EnableMouseCapture).unwrap();
+    // a panic would otherwise leave the terminal capturing the mouse with the cursor hidden
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(stdout(), DisableMouseCapture);
+        print!("\x1b[?25h");
+        hook(info);
+    }));
+
+    let mut recorder = args.record.as_ref().map(|path| {
+        Recorder::create(path, config.width(), config.height()).unwrap_or_else(|err| {
+            println!("error starting recording: {}", err);
+            panic!();
+        })
+    });
+
+    let mut replay = args.replay.as_ref().map(|path| {
+        Replay::load(path, config.keybindings()).unwrap_or_else(|err| {
+            println!("error loading replay: {}", err);
+            panic!();
+        })
+    });
+
+    let mut profiler = Profiler::windowed(60);
+    let timestep = FRAMEDELAY as Float / 1000.0;
+    let mut pacer = Pacer::cons(config.target_fps(), timestep, replay.is_some());
 
     loop {
-        let inputs = get_user_input();
-        if inputs.contains(&'p') {
+        let steps = pacer.begin();
+        let mut inputs = get_user_input(config.keybindings());
+        if let Some(replay) = &mut replay {
+            if replay.finished() {
+                if !args.looping { break; }
+                // every pass of a kiosk loop starts from the same scene
+                replay.restart();
+                config = load_config(&args);
+                (system, viewmodel, clock) = start_scene(&args, &config);
+            }
+            // only quitting and recording are left to the keyboard while a script runs
+            inputs.retain(|input| matches!(input, Action::Quit | Action::ToggleRecording));
+            inputs.extend(replay.step(FRAMEDELAY as Float / 1000.0));
+        }
+        if inputs.contains(&Action::Quit) {
             break;
         }
+        if inputs.contains(&Action::ToggleRecording) {
+            toggle_recording(&mut recorder, &config);
+        }
+        if inputs.contains(&Action::ToggleNbody) {
+            system.toggle_physics();
+        }
+        inputs.iter().for_each(|input| match input {
+            Action::SpawnProbe => {
+                let launch = viewmodel.orient.forward() * config.launch_speed();
+                system.spawn_probe(viewmodel.pos, launch);
+            }
+            Action::Burn(burn) => system.burn_probe(*burn, config.burn_dv()),
+            Action::PickTransfer => {
+                if let Some(target) = &viewmodel.target {
+                    system.pick_transfer(target);
+                }
+            }
+            Action::Lambert(date) => system.plan_lambert(*date, config.transfer_days()),
+            _ => {}
+        });
+
+        (0..steps).for_each(|_| {
+            system.advance(clock.tick(pacer.step()));
+            viewmodel.update(pacer.step(), &system);
+        });
 
+        buffer.reserve(config.panel_reserve());
+        let start = Instant::now();
         let mut renderer = Renderer::cons(&viewmodel, &mut buffer, &system, &config);
-        renderer.buffer.clear();
-        renderer.render_planets();
-        if config.render_refs() {
-            renderer.render_spacerefs();
+        renderer.quality = pacer.quality();
+        renderer.samples = samples.as_mut();
+        renderer.render_frame(&mut profiler);
+        if config.render_profile() {
+            renderer.render_profile(&profiler);
         }
-        if config.render_orbits() {
-            renderer.render_orbits();
-        }
-        renderer.render_rings();
         dump(renderer);
-        viewmodel.react(&inputs, &system, &mut config);
-        buffer.display();
+        viewmodel.react(&inputs, &system, &mut config, &mut clock);
+        profiler.measure("display", || buffer.display());
+        profiler.record("frame", start.elapsed());
     
-        print_debug(&viewmodel);
-        sleep(FRAMEDELAY);
+        print_debug(&viewmodel, &clock, &system);
+        if let Some(recorder) = &mut recorder {
+            if recorder.recording() {
+                print!(", \x1b[31mrec\x1b[0m");
+                stdout().flush().unwrap();
+            }
+            let debug = debug_line(&viewmodel, &clock, &system);
+            let frame = format!("\x1b[H{}\x1b[H{}", buffer.to_ansi(), debug);
+            recorder.frame(&frame).unwrap_or_else(|err| flash_error(err, 1000));
+        }
+        pacer.finish();
+    }
+    execute!(stdout(), DisableMouseCapture).unwrap();
+    if let Some(recorder) = recorder {
+        let path = recorder.path.clone();
+        match recorder.finish() {
+            Ok(()) => println!("recording saved to {}", path),
+            Err(err) => println!("error saving recording: {}", err),
+        }
     }
 
     // debugging stuff here. planets[3] should likely be earth, but if it crashes at this point
@@ -85,3 +232,95 @@ fn main() {
     println!("{:?}", system.planets[3].lightsource);
     println!("{:?}", system.planets[3].features);
 }
+
+fn load_config(args: &Args) -> Config {
+    let mut config = general_config(CONFIG).unwrap_or_else(|err| {
+        println!("error parsing config: {}", err);
+        panic!();
+    });
+    if let Some(fov) = args.fov {
+        config.set_fov(fov);
+    }
+    if let Some((width, height)) = args.size {
+        config.set_size(width, height);
+    }
+    if let Some((sampx, sampy)) = args.supersample {
+        config.set_supersample(sampx, sampy);
+    }
+    config
+}
+
+/// builds the system at the requested date and puts the camera in its starting place
+fn start_scene(args: &Args, config: &Config) -> (System, ViewModel, Clock) {
+    let mut system = load_system();
+    let mut viewmodel = ViewModel::new(Vec3::cons(0, 0, 0));
+    let clock = Clock::new();
+
+    // the command line wins over the config, and without either the snapshot stands
+    if let Some(date) = args.date.as_deref().or(config.epoch()) {
+        let julianday = julian_day(date).unwrap_or_else(|err| {
+            println!("error parsing date: {}", err);
+            panic!();
+        });
+        if !(ephemeris::VALIDFROM..ephemeris::VALIDTO).contains(&julianday) {
+            flash_error("dates outside 1800 to 2050 only get rough planet positions".into(), 2000);
+        }
+        let scene = julian_day(SCENEEPOCH).unwrap();
+        // bodies the table does not know are carried there along their own paths
+        system.advance(((julianday - scene) * 86400.0) as Float);
+        let names: Vec<String> = system.planets.iter().map(|planet| planet.name.clone()).collect();
+        names.iter().for_each(|name| {
+            if let Some(path) = ephemeris::path(name, julianday) {
+                system.set_path(name, path);
+            }
+        });
+    }
+    if config.nbody() {
+        system.toggle_physics();
+    }
+    place_camera(&mut viewmodel, &system, args);
+    (system, viewmodel, clock)
+}
+
+/// the first press without --record starts a new file, after that presses pause and resume
+fn toggle_recording(recorder: &mut Option<Recorder>, config: &Config) {
+    match recorder {
+        Some(recorder) => recorder.toggle(),
+        None => match Recorder::create(&Recorder::default_path(), config.width(), config.height()) {
+            Ok(created) => *recorder = Some(created),
+            Err(err) => flash_error(err, 1000),
+        },
+    }
+}
+
+fn load_system() -> System {
+    let mut sun = Planet::cons("sun".to_owned(), Vec3::cons(0, 0, 0), 695700.0,
+        Some(SUNPATH), true, None);
+    sun.gm = Some(SUNGM as f64);
+    let mut system = System::from(sun);
+    parse_config(SYSTEMCONFIG, &mut system).unwrap_or_else(|err| {
+        println!("error parsing config: {}", err);
+        panic!();
+    });
+    system.transform_mini();
+    system
+}
+
+/// starts next to earth unless the arguments say otherwise. with both a position and a target
+/// the camera stays put and turns to face the target
+fn place_camera(viewmodel: &mut ViewModel, system: &System, args: &Args) {
+    let target = args.target.as_deref().unwrap_or("earth");
+    match args.pos {
+        Some(pos) => {
+            viewmodel.pos = pos;
+            if let Some(body) = args.target.as_ref().and_then(|_| system.find(target)) {
+                viewmodel.target = Some(target.to_owned());
+                viewmodel.look_at(body.loc);
+            }
+        }
+        None => {
+            viewmodel.goto(target, system);
+            viewmodel.land(system);
+        }
+    }
+}
@@ -2,9 +2,44 @@
 
 
 
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub};
 
-use crate::{entities::{Orbit, OrbitalParams}, Float, Int};
+use crate::{entities::{Orbit, OrbitalParams}, Float, Int, PI, TAU};
+
+
+
+/// splitmix64. plenty for scattering belts, and the same seed always gives the same belt
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut mixed = self.state;
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d049bb133111eb);
+        mixed ^ (mixed >> 31)
+    }
+
+    /// uniform in 0..1
+    pub fn uniform(&mut self) -> Float {
+        (self.next_u64() >> 40) as Float / (1u64 << 24) as Float
+    }
+
+    /// standard normal, by box-muller
+    pub fn gauss(&mut self) -> Float {
+        let radius = (-2.0 * (1.0 - self.uniform()).ln()).sqrt();
+        radius * (TAU * self.uniform()).cos()
+    }
+}
+
+/// eccentricities this close above one are treated as parabolic
+pub const PARABOLIC: Float = 1e-4;
 
 
 
@@ -85,6 +120,18 @@ impl Vec3 {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
+    pub fn cross_prod(&self, other: &Vec3) -> Vec3 {
+        Vec3::cons(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(&self) -> Float {
+        self.inner_prod(self).sqrt()
+    }
+
     pub fn normalize(&mut self) {
         let length = self.inner_prod(self).sqrt();
         self.x /= length; self.y /= length; self.z /= length;
@@ -137,6 +184,102 @@ impl DivAssign<Float> for Vec3 {
     }
 }
 
+/// unit quaternion for orientations. rotating a vector takes it from the local frame to the
+/// world frame, so the columns of a camera basis are the rotated x, y and z axes
+#[derive(Debug, Clone, Copy)]
+pub struct Quat {
+    pub w: Float, pub x: Float, pub y: Float, pub z: Float,
+}
+
+impl Quat {
+    pub fn identity() -> Quat {
+        Quat { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
+    }
+
+    pub fn axis_angle(axis: Vec3, angle: Float) -> Quat {
+        let mut axis = axis;
+        axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quat { w: cos, x: axis.x * sin, y: axis.y * sin, z: axis.z * sin }
+    }
+
+    /// same convention as the old euler pair: yaw about world z, then tilt about the local y
+    pub fn yaw_pitch(yaw: Float, pitch: Float) -> Quat {
+        Quat::axis_angle(Vec3::cons(0, 0, 1), yaw) * Quat::axis_angle(Vec3::cons(0, 1, 0), -pitch)
+    }
+
+    pub fn conjugate(&self) -> Quat {
+        Quat { w: self.w, x: -self.x, y: -self.y, z: -self.z }
+    }
+
+    pub fn inner_prod(&self, other: &Quat) -> Float {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalize(&mut self) {
+        let length = self.inner_prod(self).sqrt();
+        self.w /= length; self.x /= length; self.y /= length; self.z /= length;
+    }
+
+    pub fn rotate(&self, vec: Vec3) -> Vec3 {
+        let axis = Vec3::cons(self.x, self.y, self.z);
+        let twice = axis.cross_prod(&vec) * 2.0;
+        vec + twice * self.w + axis.cross_prod(&twice)
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.rotate(Vec3::cons(1, 0, 0))
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.rotate(Vec3::cons(0, 0, 1))
+    }
+
+    pub fn slerp(&self, other: &Quat, t: Float) -> Quat {
+        let mut other = *other;
+        let mut cos = self.inner_prod(&other);
+        if cos < 0.0 {
+            other = -other;
+            cos = -cos;
+        }
+        let (from, to) = if cos > 0.9995 {
+            (1.0 - t, t)
+        }
+        else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        let mut quat = Quat {
+            w: self.w * from + other.w * to,
+            x: self.x * from + other.x * to,
+            y: self.y * from + other.y * to,
+            z: self.z * from + other.z * to,
+        };
+        quat.normalize();
+        quat
+    }
+}
+
+impl Mul for Quat {
+    type Output = Quat;
+    fn mul(self, other: Quat) -> Self::Output {
+        Quat {
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        }
+    }
+}
+
+impl Neg for Quat {
+    type Output = Quat;
+    fn neg(self) -> Self::Output {
+        Quat { w: -self.w, x: -self.x, y: -self.y, z: -self.z }
+    }
+}
+
 pub fn orbital_cartesian_transformation(orbit: &Orbit) -> Vec3 {
     let Orbit {
         params,
@@ -145,28 +288,173 @@ pub fn orbital_cartesian_transformation(orbit: &Orbit) -> Vec3 {
     } = orbit;
     
     let OrbitalParams {
-        semimajor,
         eccentricity,
-        inclination,
-        longitudeascnode,
-        argofperiapsis,
         trueanomaly,
+        ..
     } = *params;
 
-    let radius = semimajor
-        * (1.0 - eccentricity * eccentricity)
-        / (1.0 + eccentricity * trueanomaly.cos());
+    let radius = params.semilatus() / (1.0 + eccentricity * trueanomaly.cos());
     
-    let mut vec = Vec3::cons(radius * trueanomaly.cos(), radius * trueanomaly.sin(), 0.0);
-    vec.rotatez(argofperiapsis);
-    vec.rotatex(inclination);
-    vec.rotatez(longitudeascnode);
+    let vec = Vec3::cons(radius * trueanomaly.cos(), radius * trueanomaly.sin(), 0.0);
+    perifocal_to_frame(vec, params) + *barycenter
+}
+
+/// velocity along the orbit about a body of the given gm, in the units of `semimajor` per second
+pub fn orbital_velocity(params: &OrbitalParams, gm: Float) -> Vec3 {
+    let speed = (gm / params.semilatus()).sqrt();
+    let (sin, cos) = params.trueanomaly.sin_cos();
+    let vec = Vec3::cons(-speed * sin, speed * (params.eccentricity + cos), 0.0);
+    perifocal_to_frame(vec, params)
+}
+
+/// osculating elements from a position and velocity in the scene frame, the inverse of
+/// orbital_cartesian_transformation with orbital_velocity. worked in f64, and orbits with no
+/// node or no periapsis measure their angles from the x axis and the node instead
+pub fn state_to_elements(pos: Vec3, vel: Vec3, gm: Float) -> OrbitalParams {
+    // the scene frame is mirrored in x, undo that before the textbook construction
+    let pos = [-pos.x as f64, pos.y as f64, pos.z as f64];
+    let vel = [-vel.x as f64, vel.y as f64, vel.z as f64];
+    let gm = gm as f64;
+    let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let cross = |a: [f64; 3], b: [f64; 3]| {
+        [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+    };
+
+    let radius = dot(pos, pos).sqrt();
+    let momentum = cross(pos, vel);
+    let angular = dot(momentum, momentum).sqrt();
+    let normal = momentum.map(|component| component / angular);
+    let (speedsq, radial) = (dot(vel, vel), dot(pos, vel));
+    let eccvec: [f64; 3] = std::array::from_fn(|idx| {
+        ((speedsq - gm / radius) * pos[idx] - radial * vel[idx]) / gm
+    });
+    let eccentricity = dot(eccvec, eccvec).sqrt();
+
+    let inclination = normal[2].clamp(-1.0, 1.0).acos();
+    let longitudeascnode = if normal[0].hypot(normal[1]) > 1e-9 {
+        normal[0].atan2(-normal[1])
+    }
+    else {
+        0.0
+    };
+    let node = [longitudeascnode.cos(), longitudeascnode.sin(), 0.0];
+    let inplane = cross(normal, node);
+    let latitude = dot(pos, inplane).atan2(dot(pos, node));
+    let argofperiapsis = if eccentricity > 1e-9 {
+        dot(eccvec, inplane).atan2(dot(eccvec, node))
+    }
+    else {
+        0.0
+    };
+
+    let semilatus = angular * angular / gm;
+    let size = if eccentricity < 1.0 {
+        semilatus / (1.0 - eccentricity * eccentricity)
+    }
+    else {
+        semilatus / (1.0 + eccentricity)
+    };
+    let wrap = |angle: f64| angle.rem_euclid(std::f64::consts::TAU) as Float;
+    OrbitalParams::cons(size as Float, eccentricity as Float, inclination as Float,
+        wrap(longitudeascnode), wrap(argofperiapsis), wrap(latitude - argofperiapsis))
+}
+
+/// turns a vector from the orbit's own plane, periapsis along x, into the scene frame
+pub fn perifocal_to_frame(mut vec: Vec3, params: &OrbitalParams) -> Vec3 {
+    vec.rotatez(params.argofperiapsis);
+    vec.rotatex(params.inclination);
+    vec.rotatez(params.longitudeascnode);
     vec.reflx();
-    vec += *barycenter;
     vec
 }
 
 
+/// solves kepler's equation m = e - ecc * sin(e) for the eccentric anomaly. newton steps are
+/// kept inside a bracket of the root and fall back to bisection when they overshoot, so high
+/// eccentricities still converge
+pub fn solve_kepler(meananomaly: Float, eccentricity: Float) -> Float {
+    let mean = (meananomaly + PI).rem_euclid(TAU) - PI;
+    let residual = |ecc: Float| ecc - eccentricity * ecc.sin() - mean;
+    let (mut lo, mut hi) = (-PI, PI);
+    let mut ecc = if eccentricity > 0.8 { PI.copysign(mean) } else { mean };
+
+    for _ in 0..50 {
+        let value = residual(ecc);
+        if value.abs() < 1e-6 {
+            break;
+        }
+        if value > 0.0 { hi = ecc; } else { lo = ecc; }
+        let step = ecc - value / (1.0 - eccentricity * ecc.cos());
+        ecc = if step > lo && step < hi { step } else { (lo + hi) / 2.0 };
+    }
+    ecc
+}
+
+/// solves the hyperbolic kepler equation m = ecc * sinh(h) - h. the residual is monotonic in h,
+/// so the same bracketed newton scheme as the elliptic solver applies
+pub fn solve_hyperbolic(meananomaly: Float, eccentricity: Float) -> Float {
+    let residual = |anomaly: Float| eccentricity * anomaly.sinh() - anomaly - meananomaly;
+    // sinh(h) >= h, so (ecc - 1) * sinh(h) <= |m| bounds the root
+    let bound = (meananomaly.abs() / (eccentricity - 1.0)).asinh() + 1e-3;
+    let (mut lo, mut hi) = (-bound, bound);
+    let mut anomaly = (meananomaly / eccentricity).asinh();
+
+    for _ in 0..60 {
+        let value = residual(anomaly);
+        if value.abs() < 1e-6 * meananomaly.abs().max(1.0) {
+            break;
+        }
+        if value > 0.0 { hi = anomaly; } else { lo = anomaly; }
+        let step = anomaly - value / (eccentricity * anomaly.cosh() - 1.0);
+        anomaly = if step > lo && step < hi { step } else { (lo + hi) / 2.0 };
+    }
+    anomaly
+}
+
+/// barker's equation m = d + d^3 / 3 with d = tan(nu / 2), which has a closed form root
+pub fn solve_barker(meananomaly: Float) -> Float {
+    // solved for the positive side, the cubic is odd and the negative side cancels badly
+    let mean = meananomaly.abs();
+    let root = (1.5 * mean + (2.25 * mean * mean + 1.0).sqrt()).cbrt();
+    (root - 1.0 / root).copysign(meananomaly)
+}
+
+/// mean anomaly for any conic. it wraps for ellipses, while open orbits count it from
+/// periapsis with true anomaly taken in -pi..pi
+pub fn true_to_mean(trueanomaly: Float, eccentricity: Float) -> Float {
+    if eccentricity >= 1.0 {
+        let trueanomaly = (trueanomaly + PI).rem_euclid(TAU) - PI;
+        if eccentricity - 1.0 < PARABOLIC {
+            let tangent = (trueanomaly / 2.0).tan();
+            return tangent + tangent * tangent * tangent / 3.0;
+        }
+        let ratio = ((eccentricity - 1.0) / (eccentricity + 1.0)).sqrt();
+        let tangent = ratio * (trueanomaly / 2.0).tan();
+        let anomaly = 2.0 * tangent.atanh();
+        return eccentricity * anomaly.sinh() - anomaly;
+    }
+    let (sin, cos) = trueanomaly.sin_cos();
+    let ecc = ((1.0 - eccentricity * eccentricity).sqrt() * sin).atan2(eccentricity + cos);
+    (ecc - eccentricity * ecc.sin()).rem_euclid(TAU)
+}
+
+pub fn mean_to_true(meananomaly: Float, eccentricity: Float) -> Float {
+    if eccentricity >= 1.0 {
+        if eccentricity - 1.0 < PARABOLIC {
+            return 2.0 * solve_barker(meananomaly).atan();
+        }
+        let anomaly = solve_hyperbolic(meananomaly, eccentricity);
+        let ratio = ((eccentricity + 1.0) / (eccentricity - 1.0)).sqrt();
+        return 2.0 * (ratio * (anomaly / 2.0).tanh()).atan();
+    }
+    let ecc = solve_kepler(meananomaly, eccentricity);
+    let (sin, cos) = (ecc / 2.0).sin_cos();
+    let trueanomaly = 2.0 * ((1.0 + eccentricity).sqrt() * sin)
+        .atan2((1.0 - eccentricity).sqrt() * cos);
+    trueanomaly.rem_euclid(TAU)
+}
+
+
 
 #[cfg(test)]
 mod test {
@@ -189,4 +477,65 @@ mod test {
         let test = v1.inner_prod(&v2);
         assert!(test as Int == 1);
     }
+
+    #[test]
+    fn quat_matches_euler() {
+        let (yaw, pitch) = (0.7, -0.3);
+        let mut euler = Vec3::cons(1.0, 2.0, 3.0);
+        euler.rotatez(-yaw);
+        euler.rotatey(pitch);
+        let quat = Quat::yaw_pitch(yaw, pitch).conjugate().rotate(Vec3::cons(1.0, 2.0, 3.0));
+        assert!((euler - quat).length() < 1e-5);
+    }
+
+    #[test]
+    fn anomaly_roundtrip() {
+        for eccentricity in [0.0, 0.2, 0.9, 0.99] {
+            for step in 0..36 {
+                let trueanomaly = step as Float * TAU / 36.0;
+                let mean = true_to_mean(trueanomaly, eccentricity);
+                let back = mean_to_true(mean, eccentricity);
+                let error = (back - trueanomaly + PI).rem_euclid(TAU) - PI;
+                assert!(error.abs() < 1e-3, "e {} nu {} got {}", eccentricity, trueanomaly, back);
+            }
+        }
+    }
+
+    #[test]
+    fn state_roundtrip() {
+        let gm = 1.3271244e11;
+        let cases = [
+            OrbitalParams::cons(1.5e8, 0.0167, 0.4, 3.2, 4.8, 0.1),
+            OrbitalParams::cons(5.8e7, 0.2056, 0.12, 0.84, 0.51, 2.7),
+            OrbitalParams::cons(4.0e8, 0.7, 2.6, 5.9, 1.3, 4.0),
+            OrbitalParams::cons(3.8e7, 1.2, 2.14, 0.43, 4.22, 5.5),
+        ];
+        for params in cases {
+            let orbit = Orbit::cons(params, Vec3::cons(0, 0, 0), false);
+            let pos = orbital_cartesian_transformation(&orbit);
+            let vel = orbital_velocity(&params, gm);
+            let back = state_to_elements(pos, vel, gm);
+            let angle = |a: Float, b: Float| ((a - b + PI).rem_euclid(TAU) - PI).abs();
+            assert!((back.semimajor / params.semimajor - 1.0).abs() < 1e-4, "{:?}", back);
+            assert!((back.eccentricity - params.eccentricity).abs() < 1e-4, "{:?}", back);
+            assert!(angle(back.inclination, params.inclination) < 1e-3, "{:?}", back);
+            assert!(angle(back.longitudeascnode, params.longitudeascnode) < 1e-3, "{:?}", back);
+            assert!(angle(back.argofperiapsis, params.argofperiapsis) < 1e-3, "{:?}", back);
+            assert!(angle(back.trueanomaly, params.trueanomaly) < 1e-3, "{:?}", back);
+        }
+    }
+
+    #[test]
+    fn open_anomaly_roundtrip() {
+        for eccentricity in [1.0, 1.05, 1.5, 4.0] {
+            let limit = (-1.0 / eccentricity as Float).acos() * 0.95;
+            for step in -20..=20 {
+                let trueanomaly = step as Float / 20.0 * limit;
+                let mean = true_to_mean(trueanomaly, eccentricity);
+                let back = mean_to_true(mean, eccentricity);
+                let error = (back - trueanomaly).abs();
+                assert!(error < 1e-3, "e {} nu {} got {}", eccentricity, trueanomaly, back);
+            }
+        }
+    }
 }
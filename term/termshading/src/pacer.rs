@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use crate::Float;
+
+
+
+/// the longest stretch of real time simulated in one frame. after a stall the sim falls behind
+/// instead of spiralling into ever longer catch-up frames
+const MAXCATCHUP: Float = 0.25;
+const MINQUALITY: Float = 0.25;
+
+
+
+/// keeps the loop at a target frame rate. the simulation advances in fixed steps fed by real
+/// elapsed time, and the sample density of the heavier passes drops while frames run over budget
+pub struct Pacer {
+    budget: Duration,
+    step: Float,
+    lockstep: bool,
+    accumulator: Float,
+    last: Instant,
+    start: Instant,
+    quality: Float,
+}
+
+impl Pacer {
+    /// in lockstep every frame gets exactly one step, so scripted replays stay deterministic
+    pub fn cons(targetfps: Float, step: Float, lockstep: bool) -> Pacer {
+        let now = Instant::now();
+        Pacer {
+            budget: Duration::from_secs_f32(1.0 / targetfps.max(1.0)),
+            step,
+            lockstep,
+            accumulator: 0.0,
+            last: now,
+            start: now,
+            quality: 1.0,
+        }
+    }
+
+    pub fn step(&self) -> Float {
+        self.step
+    }
+
+    pub fn quality(&self) -> Float {
+        self.quality
+    }
+
+    /// starts a frame and returns how many simulation steps are owed since the last one
+    pub fn begin(&mut self) -> usize {
+        let now = Instant::now();
+        let elapsed = (now - self.last).as_secs_f32().min(MAXCATCHUP);
+        self.last = now;
+        self.start = now;
+        if self.lockstep {
+            return 1;
+        }
+        self.accumulator += elapsed;
+        let steps = (self.accumulator / self.step) as usize;
+        self.accumulator -= steps as Float * self.step;
+        steps
+    }
+
+    /// adapts quality to how long the frame took, then sleeps away whatever budget is left
+    pub fn finish(&mut self) {
+        let frametime = self.start.elapsed();
+        if frametime > self.budget {
+            self.quality = (self.quality * 0.85).max(MINQUALITY);
+        }
+        else if frametime < self.budget.mul_f32(0.7) {
+            self.quality = (self.quality * 1.05).min(1.0);
+        }
+        if let Some(remaining) = self.budget.checked_sub(frametime) {
+            std::thread::sleep(remaining);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::entities::{Orbit, Planet};
+use crate::math::{orbital_cartesian_transformation, orbital_velocity, Vec3};
+use crate::{Float, DISTANCESCALE};
+
+
+
+/// km^3 / (kg s^2), turns `mass=` into a gravitational parameter
+pub const GRAVITATIONAL: f64 = 6.6743e-20;
+/// longest single leapfrog step in seconds. high warps are split into this many substeps
+const MAXSTEP: f64 = 21600.0;
+
+
+
+pub type State = [f64; 3];
+
+/// direct n-body integration of every body with a path, plus the sun, in km and km/s. state is
+/// kept in f64 and only rounded down to scene units when handed back to the system
+#[derive(Debug)]
+pub struct Physics {
+    bodies: Vec<Body>,
+    initial: f64,
+}
+
+#[derive(Debug)]
+struct Body {
+    name: String,
+    gm: f64,
+    pos: State,
+    vel: State,
+    start: Vec3,
+}
+
+impl Physics {
+    /// state vectors come from each body's current elements about the sun. bodies without a
+    /// `gm=` or `mass=` key still move but pull on nothing. the sun is given the opposite
+    /// momentum of the planets so the barycenter stays put
+    pub fn from(planets: &[Planet], sungm: f64) -> Physics {
+        let mut bodies: Vec<Body> = planets.iter().filter_map(|planet| {
+            let (pos, vel) = state_vectors(planet.path()?, sungm);
+            let gm = planet.gm.unwrap_or(0.0);
+            Some(Body { name: planet.name.clone(), gm, pos, vel, start: planet.loc })
+        }).collect();
+
+        let anchors: Vec<&Planet> = planets.iter().filter(|planet| {
+            planet.path().is_none() && planet.parent.is_none() && planet.gm.is_some()
+        }).collect();
+        let momentum = bodies.iter().fold([0.0; 3], |sum, body| add(sum, scale(body.vel, body.gm)));
+        let anchorgm: f64 = anchors.iter().filter_map(|planet| planet.gm).sum();
+        anchors.iter().for_each(|planet| {
+            bodies.push(Body {
+                name: planet.name.clone(), gm: planet.gm.unwrap_or(0.0),
+                pos: to_state(planet.loc * DISTANCESCALE), vel: scale(momentum, -1.0 / anchorgm),
+                start: planet.loc,
+            });
+        });
+
+        let mut physics = Physics { bodies, initial: 0.0 };
+        physics.initial = physics.energy();
+        physics
+    }
+
+    /// kick drift kick leapfrog, symplectic so energy error stays bounded instead of growing
+    pub fn advance(&mut self, seconds: f64) {
+        let steps = (seconds.abs() / MAXSTEP).ceil().max(1.0);
+        let dt = seconds / steps;
+        (0..steps as usize).for_each(|_| {
+            let accels = self.accelerations();
+            self.bodies.iter_mut().zip(&accels).for_each(|(body, accel)| {
+                body.vel = add(body.vel, scale(*accel, dt / 2.0));
+                body.pos = add(body.pos, scale(body.vel, dt));
+            });
+            let accels = self.accelerations();
+            self.bodies.iter_mut().zip(&accels).for_each(|(body, accel)| {
+                body.vel = add(body.vel, scale(*accel, dt / 2.0));
+            });
+        });
+    }
+
+    fn accelerations(&self) -> Vec<State> {
+        self.bodies.iter().enumerate().map(|(idx, body)| {
+            self.bodies.iter().enumerate()
+                .filter(|(other, attractor)| *other != idx && attractor.gm > 0.0)
+                .fold([0.0; 3], |accel, (_, attractor)| {
+                    let relative = sub(attractor.pos, body.pos);
+                    let distance = norm(relative);
+                    add(accel, scale(relative, attractor.gm / (distance * distance * distance)))
+                })
+        }).collect()
+    }
+
+    /// total energy scaled by the gravitational constant, which drift does not care about
+    pub fn energy(&self) -> f64 {
+        let kinetic: f64 = self.bodies.iter().map(|body| {
+            0.5 * body.gm * dot(body.vel, body.vel)
+        }).sum();
+        let potential: f64 = self.bodies.iter().enumerate().map(|(idx, body)| {
+            self.bodies.iter().skip(idx + 1).map(|other| {
+                -body.gm * other.gm / norm(sub(other.pos, body.pos))
+            }).sum::<f64>()
+        }).sum();
+        kinetic + potential
+    }
+
+    /// relative change in energy since integration started
+    pub fn drift(&self) -> f64 {
+        if self.initial == 0.0 { return 0.0; }
+        (self.energy() - self.initial) / self.initial.abs()
+    }
+
+    /// where every body is now, in scene units
+    pub fn positions(&self) -> impl Iterator<Item = (&str, Vec3)> {
+        self.bodies.iter().map(|body| (body.name.as_str(), to_vec(body.pos) / DISTANCESCALE))
+    }
+
+    /// where every body was when integration started, in scene units
+    pub fn starts(&self) -> impl Iterator<Item = (&str, Vec3)> {
+        self.bodies.iter().map(|body| (body.name.as_str(), body.start))
+    }
+}
+
+/// heliocentric position and velocity in km and km/s for a path in scene units, any conic
+pub fn state_vectors(orbit: &Orbit, gm: f64) -> (State, State) {
+    let mut params = orbit.params;
+    params.semimajor *= DISTANCESCALE;
+    let pos = orbital_cartesian_transformation(orbit) * DISTANCESCALE;
+    (to_state(pos), to_state(orbital_velocity(&params, gm as Float)))
+}
+
+pub fn to_state(vec: Vec3) -> State {
+    [vec.x as f64, vec.y as f64, vec.z as f64]
+}
+
+pub fn to_vec(state: State) -> Vec3 {
+    Vec3::cons(state[0] as Float, state[1] as Float, state[2] as Float)
+}
+
+pub fn add(a: State, b: State) -> State {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn sub(a: State, b: State) -> State {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn scale(a: State, factor: f64) -> State {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+pub fn dot(a: State, b: State) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn norm(a: State) -> f64 {
+    dot(a, a).sqrt()
+}
+
+pub fn cross(a: State, b: State) -> State {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn body(name: &str, gm: f64, pos: State, vel: State) -> Body {
+        Body { name: name.to_owned(), gm, pos, vel, start: Vec3::cons(0, 0, 0) }
+    }
+
+    #[test]
+    fn circular_orbit() {
+        let (sungm, radius): (f64, f64) = (1.3271244e11, 1.496e8);
+        let speed = (sungm / radius).sqrt();
+        let mut physics = Physics {
+            bodies: vec![
+                body("sun", sungm, [0.0; 3], [0.0; 3]),
+                body("probe", 0.0, [radius, 0.0, 0.0], [0.0, speed, 0.0]),
+            ],
+            initial: 0.0,
+        };
+        let period = std::f64::consts::TAU * (radius * radius * radius / sungm).sqrt();
+        let start = physics.bodies[1].pos;
+        physics.advance(period);
+        let drift = norm(sub(physics.bodies[1].pos, start)) / radius;
+        assert!(drift < 1e-3, "came back {} radii off", drift);
+        assert!((norm(physics.bodies[1].vel) - speed).abs() / speed < 1e-4);
+    }
+}
@@ -0,0 +1,334 @@
+use std::f64::consts::TAU;
+
+use crate::entities::{Orbit, Planet};
+use crate::math::{orbital_cartesian_transformation, Vec3};
+use crate::physics::{add, cross, dot, norm, scale, sub, to_state, to_vec, State};
+use crate::{Float, DISTANCESCALE};
+
+
+
+/// name of the body that stands in for the probe, so it can be labelled and targeted
+pub const PROBENAME: &str = "probe";
+/// longest step in seconds, and the share of the local free fall time a step may take
+const MAXSTEP: f64 = 21600.0;
+const STEPFRACTION: f64 = 0.02;
+/// furthest ahead the path is predicted, in seconds
+const MAXHORIZON: f64 = 2.0 * 365.25 * 86400.0;
+/// seconds between points of the predicted path
+const PATHSPACING: f64 = 43200.0;
+
+
+
+/// directions a burn can be made in, taken against the probe's motion about the sun
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Burn {
+    Prograde,
+    Retrograde,
+    Normal,
+    Antinormal,
+    RadialOut,
+    RadialIn,
+}
+
+impl Burn {
+    pub fn parse(name: &str) -> Option<Burn> {
+        match name {
+            "prograde" => Some(Burn::Prograde),
+            "retrograde" => Some(Burn::Retrograde),
+            "normal" => Some(Burn::Normal),
+            "antinormal" => Some(Burn::Antinormal),
+            "radial_out" => Some(Burn::RadialOut),
+            "radial_in" => Some(Burn::RadialIn),
+            _ => None,
+        }
+    }
+}
+
+/// a body the probe falls toward. planets are carried along their own paths while the probe
+/// is integrated, so predictions see them where they will be
+#[derive(Debug, Clone)]
+pub struct Attractor {
+    name: String,
+    gm: f64,
+    radius: f64,
+    path: Option<Orbit>,
+    loc: Vec3,
+}
+
+impl Attractor {
+    /// every body with a gravitational parameter, less moons whose offsets are exaggerated
+    pub fn gather(planets: &[Planet]) -> Vec<Attractor> {
+        planets.iter().filter(|planet| planet.parent.is_none()).filter_map(|planet| {
+            let path = planet.path().copied();
+            Some(Attractor {
+                name: planet.name.clone(), gm: planet.gm?, radius: planet.physrad as f64,
+                loc: path.as_ref().map_or(planet.loc, orbital_cartesian_transformation), path,
+            })
+        }).collect()
+    }
+
+    /// moves along the path to `offset` seconds after it was gathered, in one go from the
+    /// start so rounding does not pile up over small steps
+    fn at(&mut self, offset: f64) {
+        if let Some(mut path) = self.path {
+            path.advance(offset as Float);
+            self.loc = orbital_cartesian_transformation(&path);
+        }
+    }
+
+    fn pos(&self) -> State {
+        to_state(self.loc * DISTANCESCALE)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Approach {
+    pub name: String,
+    /// km between centers
+    pub distance: f64,
+    /// probe time it happens at
+    pub time: f64,
+}
+
+/// a massless craft integrated in km and km/s about the sun, with its path predicted ahead
+#[derive(Debug)]
+pub struct Probe {
+    pos: State,
+    vel: State,
+    /// seconds since spawning
+    pub elapsed: f64,
+    pub crashed: Option<String>,
+    /// points ahead in scene units, with the probe time each is reached at
+    pub prediction: Vec<(f64, Vec3)>,
+    pub approaches: Vec<Approach>,
+    predicted: f64,
+    horizon: f64,
+}
+
+impl Probe {
+    /// at a point in scene units, moving at a velocity in km/s
+    pub fn spawn(loc: Vec3, vel: Vec3, attractors: &[Attractor]) -> Probe {
+        let mut probe = Probe {
+            pos: to_state(loc * DISTANCESCALE), vel: to_state(vel), elapsed: 0.0, crashed: None,
+            prediction: Vec::new(), approaches: Vec::new(), predicted: 0.0, horizon: MAXHORIZON,
+        };
+        probe.predict(attractors);
+        probe
+    }
+
+    pub fn loc(&self) -> Vec3 {
+        to_vec(self.pos) / DISTANCESCALE
+    }
+
+    /// km/s relative to the sun
+    pub fn speed(&self) -> f64 {
+        norm(self.vel)
+    }
+
+    /// an instant change of velocity by `deltav` km/s. normal is along the orbit's angular
+    /// momentum and radial out is away from the sun across the direction of motion
+    pub fn burn(&mut self, burn: Burn, deltav: f64, attractors: &[Attractor]) {
+        if self.crashed.is_some() { return; }
+        let prograde = scale(self.vel, 1.0 / norm(self.vel).max(f64::EPSILON));
+        // the scene is mirrored in x, which flips the handedness of cross products
+        let normal = scale(cross(self.pos, self.vel), -1.0);
+        let normal = scale(normal, 1.0 / norm(normal).max(f64::EPSILON));
+        let radial = scale(cross(prograde, normal), -1.0);
+        let direction = match burn {
+            Burn::Prograde => prograde,
+            Burn::Retrograde => scale(prograde, -1.0),
+            Burn::Normal => normal,
+            Burn::Antinormal => scale(normal, -1.0),
+            Burn::RadialOut => radial,
+            Burn::RadialIn => scale(radial, -1.0),
+        };
+        self.vel = add(self.vel, scale(direction, deltav));
+        self.predict(attractors);
+    }
+
+    /// flies the probe `seconds` on from where the attractors were gathered. stops for good on
+    /// hitting a body
+    pub fn advance(&mut self, seconds: f64, attractors: &[Attractor]) {
+        if self.crashed.is_some() { return; }
+        let mut attractors = attractors.to_vec();
+        let mut crashed = None;
+        fly(&mut self.pos, &mut self.vel, &mut attractors, seconds, |_, pos, attractors| {
+            crashed = impact(pos, attractors);
+            crashed.is_none()
+        });
+        self.crashed = crashed;
+        self.elapsed += seconds;
+    }
+
+    /// predicts again once the probe is a good way along the old prediction, or has passed an
+    /// approach that was still ahead when it was made
+    pub fn refresh(&mut self, attractors: &[Attractor]) {
+        let passed = self.approaches.iter().any(|approach| {
+            approach.time > self.predicted && approach.time <= self.elapsed
+        });
+        if passed || self.elapsed - self.predicted > self.horizon / 200.0 {
+            self.predict(attractors);
+        }
+    }
+
+    /// one orbit about the sun ahead, up to a limit, noting the closest approach to each
+    /// planet along the way
+    fn predict(&mut self, attractors: &[Attractor]) {
+        let sun = attractors.iter().filter(|attractor| attractor.path.is_none())
+            .max_by(|one, two| one.gm.total_cmp(&two.gm));
+        self.horizon = sun.map_or(MAXHORIZON, |sun| {
+            let relative = sub(self.pos, sun.pos());
+            let energy = dot(self.vel, self.vel) / 2.0 - sun.gm / norm(relative);
+            if energy >= 0.0 { return MAXHORIZON; }
+            let semimajor = -sun.gm / (2.0 * energy);
+            (TAU * (semimajor * semimajor * semimajor / sun.gm).sqrt()).min(MAXHORIZON)
+        });
+
+        let now = self.elapsed;
+        let (mut pos, mut vel) = (self.pos, self.vel);
+        let mut attractors = attractors.to_vec();
+        let mut approaches: Vec<Approach> = attractors.iter()
+            .filter(|attractor| attractor.path.is_some())
+            .map(|planet| Approach { name: planet.name.clone(), distance: f64::MAX, time: now })
+            .collect();
+        let mut prediction = vec![(now, self.loc())];
+        let mut last = 0.0;
+        fly(&mut pos, &mut vel, &mut attractors, self.horizon, |time, pos, attractors| {
+            let planets = attractors.iter().filter(|attractor| attractor.path.is_some());
+            planets.zip(approaches.iter_mut()).for_each(|(planet, approach)| {
+                let distance = norm(sub(planet.pos(), *pos));
+                if distance < approach.distance {
+                    (approach.distance, approach.time) = (distance, now + time);
+                }
+            });
+            if time - last >= PATHSPACING {
+                prediction.push((now + time, to_vec(*pos) / DISTANCESCALE));
+                last = time;
+            }
+            impact(pos, attractors).is_none()
+        });
+        prediction.push((now + self.horizon, to_vec(pos) / DISTANCESCALE));
+        (self.prediction, self.approaches, self.predicted) = (prediction, approaches, now);
+    }
+}
+
+/// kick drift kick with steps held to a fraction of the free fall time of the nearest pull,
+/// so flybys get fine steps and cruise gets long ones. `visit` sees every step and stops the
+/// flight by returning false
+fn fly(
+    pos: &mut State, vel: &mut State, attractors: &mut [Attractor], seconds: f64,
+    mut visit: impl FnMut(f64, &State, &[Attractor]) -> bool
+) {
+    let mut accel = acceleration(pos, attractors);
+    let mut done = 0.0;
+    while done < seconds {
+        let dt = attractors.iter().map(|attractor| {
+            let distance = norm(sub(attractor.pos(), *pos));
+            STEPFRACTION * (distance * distance * distance / attractor.gm).sqrt()
+        }).fold(MAXSTEP, f64::min).max(1.0).min(seconds - done);
+        *vel = add(*vel, scale(accel, dt / 2.0));
+        *pos = add(*pos, scale(*vel, dt));
+        done += dt;
+        attractors.iter_mut().for_each(|attractor| attractor.at(done));
+        accel = acceleration(pos, attractors);
+        *vel = add(*vel, scale(accel, dt / 2.0));
+        if !visit(done, pos, attractors) { return; }
+    }
+}
+
+fn acceleration(pos: &State, attractors: &[Attractor]) -> State {
+    attractors.iter().fold([0.0; 3], |accel, attractor| {
+        let relative = sub(attractor.pos(), *pos);
+        let distance = norm(relative);
+        add(accel, scale(relative, attractor.gm / (distance * distance * distance)))
+    })
+}
+
+fn impact(pos: &State, attractors: &[Attractor]) -> Option<String> {
+    attractors.iter().find(|attractor| norm(sub(attractor.pos(), *pos)) < attractor.radius)
+        .map(|attractor| attractor.name.clone())
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::OrbitalParams;
+
+    const SUNGM: f64 = 1.3271244e11;
+    const AU: f64 = 1.496e8;
+
+    fn sun() -> Attractor {
+        Attractor {
+            name: "sun".to_owned(), gm: SUNGM, radius: 695700.0, path: None,
+            loc: Vec3::cons(0, 0, 0),
+        }
+    }
+
+    /// at `radius` km on +x, going prograde, which the mirrored x makes -y
+    fn circling(radius: f64, speed: f64) -> Probe {
+        let loc = Vec3::cons(radius as Float, 0.0, 0.0) / DISTANCESCALE;
+        Probe::spawn(loc, Vec3::cons(0.0, -speed as Float, 0.0), &[sun()])
+    }
+
+    #[test]
+    fn burn_directions() {
+        let speed = (SUNGM / AU).sqrt();
+        let expected = [
+            (Burn::Prograde, [0.0, -speed - 1.0, 0.0]),
+            (Burn::Retrograde, [0.0, -speed + 1.0, 0.0]),
+            (Burn::Normal, [0.0, -speed, 1.0]),
+            (Burn::Antinormal, [0.0, -speed, -1.0]),
+            (Burn::RadialOut, [1.0, -speed, 0.0]),
+            (Burn::RadialIn, [-1.0, -speed, 0.0]),
+        ];
+        expected.iter().for_each(|(burn, vel)| {
+            let mut probe = circling(AU, speed);
+            probe.burn(*burn, 1.0, &[sun()]);
+            assert!(norm(sub(probe.vel, *vel)) < 1e-3, "{:?} gave {:?}", burn, probe.vel);
+        });
+    }
+
+    #[test]
+    fn horizon() {
+        // a bound probe is predicted one orbit ahead, an escaping one as far as allowed
+        let probe = circling(AU, (SUNGM / AU).sqrt());
+        let period = TAU * (AU * AU * AU / SUNGM).sqrt();
+        assert!((probe.horizon - period).abs() / period < 1e-3, "horizon {}", probe.horizon);
+        assert!(probe.prediction.last().unwrap().0 == probe.horizon);
+        let probe = circling(AU, 60.0);
+        assert!(probe.horizon == MAXHORIZON, "escaping horizon {}", probe.horizon);
+    }
+
+    #[test]
+    fn approach() {
+        // a fixed rock of no pull, passed 1e5 km off at 1 km/s a million seconds in
+        let params = OrbitalParams::cons(AU as Float / DISTANCESCALE, 0.0, 0.0, 0.0, 0.0, 0.0);
+        let mut path = Orbit::cons(params, Vec3::cons(0, 0, 0), false);
+        path.meanmotion = 0.0;
+        let loc = orbital_cartesian_transformation(&path);
+        let rock = Attractor {
+            name: "rock".to_owned(), gm: 1.0, radius: 1.0, path: Some(path), loc,
+        };
+        let start = loc + Vec3::cons(-1e6, 1e5, 0.0) / DISTANCESCALE;
+        let probe = Probe::spawn(start, Vec3::cons(1, 0, 0), &[rock]);
+        let approach = &probe.approaches[0];
+        assert!(approach.name == "rock");
+        assert!((approach.distance - 1e5).abs() < 1e3, "distance {}", approach.distance);
+        assert!((approach.time - 1e6).abs() < MAXSTEP, "time {}", approach.time);
+    }
+
+    #[test]
+    fn crash() {
+        // dropped a million km over the sun it falls in within the hour
+        let mut probe = circling(1e6, 0.0);
+        probe.advance(86400.0, &[sun()]);
+        assert!(probe.crashed.as_deref() == Some("sun"), "crashed {:?}", probe.crashed);
+        assert!(norm(probe.pos) < 695700.0);
+        let (pos, elapsed) = (probe.pos, probe.elapsed);
+        probe.burn(Burn::Prograde, 10.0, &[sun()]);
+        probe.advance(86400.0, &[sun()]);
+        assert!(probe.pos == pos && probe.elapsed == elapsed, "a crashed probe moved on");
+    }
+}
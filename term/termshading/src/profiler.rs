@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+
+
+/// collects wall time per render pass. interactive use keeps a short window of recent frames
+/// for the overlay, benchmarks keep everything
+pub struct Profiler {
+    passes: Vec<Pass>,
+    window: Option<usize>,
+}
+
+struct Pass {
+    name: &'static str,
+    samples: Vec<Duration>,
+}
+
+pub struct PassSummary {
+    pub name: &'static str,
+    pub last: Duration,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p99: Duration,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler { passes: Vec::new(), window: None }
+    }
+
+    pub fn windowed(frames: usize) -> Profiler {
+        Profiler { passes: Vec::new(), window: Some(frames) }
+    }
+
+    pub fn measure<T>(&mut self, name: &'static str, pass: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = pass();
+        self.record(name, start.elapsed());
+        result
+    }
+
+    pub fn record(&mut self, name: &'static str, time: Duration) {
+        let idx = match self.passes.iter().position(|pass| pass.name == name) {
+            Some(idx) => idx,
+            None => {
+                self.passes.push(Pass { name, samples: Vec::new() });
+                self.passes.len() - 1
+            }
+        };
+        let samples = &mut self.passes[idx].samples;
+        samples.push(time);
+        if let Some(window) = self.window {
+            if samples.len() > window {
+                samples.remove(0);
+            }
+        }
+    }
+
+    pub fn summary(&self) -> Vec<PassSummary> {
+        self.passes.iter().filter(|pass| !pass.samples.is_empty()).map(|pass| {
+            let mut sorted = pass.samples.clone();
+            sorted.sort();
+            let p99 = ((sorted.len() as f64 * 0.99).ceil() as usize).clamp(1, sorted.len()) - 1;
+            PassSummary {
+                name: pass.name,
+                last: *pass.samples.last().unwrap(),
+                min: sorted[0],
+                mean: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+                p99: sorted[p99],
+            }
+        }).collect()
+    }
+
+    pub fn report(&self) -> String {
+        let mut report =
+            format!("{:<18}{:>10}{:>10}{:>10}\n", "pass", "min ms", "mean ms", "p99 ms");
+        self.summary().iter().for_each(|pass| {
+            report.push_str(&format!("{:<18}{:>10.3}{:>10.3}{:>10.3}\n", pass.name,
+                millis(pass.min), millis(pass.mean), millis(pass.p99)));
+        });
+        report
+    }
+
+    pub fn overlay(&self) -> Vec<String> {
+        self.summary().iter().map(|pass| {
+            format!("{:<16}{:>7.2} {:>7.2} ms", pass.name, millis(pass.last), millis(pass.mean))
+        }).collect()
+    }
+}
+
+fn millis(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,98 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::Int;
+
+
+
+/// writes frames to an asciicast v2 file, which any asciinema player can replay. time spent
+/// paused is cut out so the recording plays back without gaps
+pub struct Recorder {
+    file: BufWriter<File>,
+    pub path: String,
+    recorded: f64,
+    resumed: Option<Instant>,
+}
+
+impl Recorder {
+    pub fn create(path: &str, width: Int, height: Int) -> Result<Recorder, Box<dyn Error>> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        writeln!(file, "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}}}",
+            width, height, timestamp)?;
+        let mut recorder = Recorder {
+            file, path: path.to_owned(), recorded: 0.0, resumed: Some(Instant::now()),
+        };
+        // the same clear and hidden cursor the viewer starts with
+        recorder.frame("\x1b[2J\x1b[?25l")?;
+        Ok(recorder)
+    }
+
+    /// a fresh file named after the current time, for recordings started from the keyboard
+    pub fn default_path() -> String {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs()).unwrap_or(0);
+        format!("termshading-{}.cast", timestamp)
+    }
+
+    pub fn recording(&self) -> bool {
+        self.resumed.is_some()
+    }
+
+    pub fn toggle(&mut self) {
+        match self.resumed.take() {
+            Some(resumed) => self.recorded += resumed.elapsed().as_secs_f64(),
+            None => self.resumed = Some(Instant::now()),
+        }
+    }
+
+    /// records terminal output as written. display leans on the tty turning newlines into
+    /// carriage return and newline, which a player will not do, so that happens here
+    pub fn frame(&mut self, output: &str) -> Result<(), Box<dyn Error>> {
+        let Some(resumed) = self.resumed else { return Ok(()); };
+        let time = self.recorded + resumed.elapsed().as_secs_f64();
+        let output = output.replace('\n', "\r\n");
+        writeln!(self.file, "[{:.6}, \"o\", \"{}\"]", time, json_escape(&output))?;
+        // one write per frame keeps the file playable if the viewer is killed
+        self.file.flush()?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Box<dyn Error>> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+fn json_escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    string.chars().for_each(|chr| {
+        match chr {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            chr if (chr as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", chr as u32)),
+            chr => escaped.push(chr),
+        }
+    });
+    escaped
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escapes_json() {
+        assert_eq!(json_escape("say \"hi\""), "say \\\"hi\\\"");
+        assert_eq!(json_escape("a\\b"), "a\\\\b");
+        assert_eq!(json_escape("\x1b[2J\n\r\t"), "\\u001b[2J\\n\\r\\t");
+        assert_eq!(json_escape("\u{0}\u{1f} \u{b0}"), "\\u0000\\u001f \u{b0}");
+    }
+}
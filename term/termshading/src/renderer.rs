@@ -5,9 +5,26 @@ use std::fs::read_to_string;
 use std::io::{stdout, Write};
 
 use crate::configparser::Config;
-use crate::entities::{Feature, Orbit, PlanetParams, Ring, SpacialReference};
-use crate::{Float, Int, Planet, System, ViewModel, PI, TAU};
-use crate::math::{orbital_cartesian_transformation, Vec3};
+use crate::entities::{Comet, Feature, Orbit, PlanetParams, Ring, SpacialReference};
+use crate::{Float, Int, Planet, System, ViewModel, AU, DISTANCESCALE, PI, SCENEEPOCH, TAU};
+use crate::math::{orbital_cartesian_transformation, orbital_velocity, Rng, Vec3};
+use crate::profiler::Profiler;
+use crate::lagrange::lagrange_points;
+use crate::trajectory::Trajectory;
+use crate::transfer::Hohmann;
+use crate::utils::{format_date, julian_day};
+
+
+
+/// orbits start as this many arcs before being split to fit the screen
+const ORBITSEGMENTS: Int = 32;
+const ORBITDEPTH: Int = 10;
+const NEARPLANE: Float = 1e-3;
+/// open orbits are drawn out to at least this many au from the focus
+const OPENREACH: Float = 50.0;
+/// comet particles per sample of tail length on screen. capped at one each of ion, dust and
+/// glow per cell drawn to, so a comet filling the view costs about as much as the view itself
+const COMETPARTICLES: Float = 6.0;
 
 
 
@@ -16,13 +33,131 @@ pub struct Renderer<'d> {
     pub buffer: &'d mut Buffer,
     pub system: &'d System,
     pub config: &'d Config,
+    /// fraction of full sample density for planets and rings, lowered by the frame pacer
+    pub quality: Float,
+    /// supersampled target for the scene passes, box filtered onto the buffer afterwards
+    pub samples: Option<&'d mut Buffer>,
 }
 
 impl<'d> Renderer<'d> {
     pub fn cons(
         view: &'d ViewModel, buff: &'d mut Buffer, sys: &'d System, config: &'d Config
     ) -> Renderer<'d> {
-        Renderer { viewmodel: view, buffer: buff, system: sys, config }
+        Renderer { viewmodel: view, buffer: buff, system: sys, config, quality: 1.0, samples: None }
+    }
+
+    /// clears the buffer and runs every pass the config has switched on, timing each. with a
+    /// sample buffer the scene is drawn there and resolved down before labels and the panel
+    pub fn render_frame(&mut self, profiler: &mut Profiler) {
+        self.buffer.clear();
+        // worked out once for both the drawn ellipse and the panel
+        let hohmann = self.system.transfer.as_ref()
+            .and_then(|transfer| transfer.hohmann(self.system));
+        match self.samples.take() {
+            Some(samples) => {
+                samples.clear();
+                samples.reserve(self.buffer.reserved * samples.sampx);
+                let mut scene = Renderer::cons(self.viewmodel, samples, self.system, self.config);
+                scene.quality = self.quality;
+                scene.render_scene(profiler, hohmann.as_ref());
+                profiler.measure("resolve", || self.buffer.resolve(scene.buffer));
+            }
+            None => self.render_scene(profiler, hohmann.as_ref()),
+        }
+        if self.config.render_labels() {
+            profiler.measure("render_labels", || self.render_labels());
+        }
+        if self.config.render_lagrange() {
+            profiler.measure("render_lagrange", || self.render_lagrange());
+        }
+        if self.config.render_panel() {
+            profiler.measure("render_panel", || self.render_panel(hohmann.as_ref()));
+        }
+        if self.system.probe.is_some() {
+            self.render_probe_hud();
+        }
+    }
+
+    fn render_scene(&mut self, profiler: &mut Profiler, hohmann: Option<&Hohmann>) {
+        profiler.measure("render_planets", || self.render_planets());
+        profiler.measure("render_belts", || self.render_belts());
+        if self.config.render_refs() {
+            profiler.measure("render_spacerefs", || self.render_spacerefs());
+        }
+        if self.config.render_orbits() {
+            profiler.measure("render_orbits", || self.render_orbits());
+            profiler.measure("render_trajectories", || self.render_trajectories());
+        }
+        profiler.measure("render_rings", || self.render_rings());
+        if self.system.probe.is_some() {
+            profiler.measure("render_probe", || self.render_probe());
+        }
+        if self.system.transfer.is_some() {
+            profiler.measure("render_transfer", || self.render_transfer(hohmann));
+        }
+        // glows go last so they are depth tested against everything solid
+        profiler.measure("render_comets", || self.render_comets());
+    }
+
+    /// the probe's predicted path, from where it is now to the end of the prediction
+    pub fn render_probe(&mut self) {
+        let Some(probe) = &self.system.probe else { return; };
+        let color = Color::cons(220, 90, 200);
+        let ahead = probe.prediction.iter().filter(|(time, _)| *time > probe.elapsed);
+        let mut from = self.world_to_view(&probe.loc());
+        ahead.for_each(|(_, point)| {
+            let to = self.world_to_view(point);
+            self.draw_segment(from, to, Some(color), None);
+            from = to;
+        });
+    }
+
+    /// the hohmann ellipse from where the departure body is now, and any lambert arc
+    pub fn render_transfer(&mut self, hohmann: Option<&Hohmann>) {
+        let Some(transfer) = &self.system.transfer else { return; };
+        let Some(from) = self.system.find(&transfer.from) else { return; };
+        if let Some(hohmann) = hohmann {
+            self.render_arc(&hohmann.path, from, hohmann.sweep, Some(Color::cons(90, 200, 120)));
+        }
+        if let Some(Ok(lambert)) = &transfer.lambert {
+            self.render_arc(&lambert.path, from, lambert.sweep, Some(Color::cons(240, 150, 60)));
+        }
+    }
+
+    /// speed, burn size and the closest approach to each planet, in the bottom left
+    pub fn render_probe_hud(&mut self) {
+        let Some(probe) = &self.system.probe else { return; };
+        let mut lines = vec![match &probe.crashed {
+            Some(body) => format!("probe impacted {}", body),
+            None => format!("probe {:.2} km/s, burns of {:.2} km/s", probe.speed(),
+                self.config.burn_dv()),
+        }];
+        if probe.crashed.is_none() {
+            lines.push("closest approaches".to_owned());
+            lines.extend(probe.approaches.iter().map(|approach| {
+                let days = (approach.time - probe.elapsed) / 86400.0;
+                format!("{:<10}{:>11.4e} km {:>8.1} d", approach.name, approach.distance, days)
+            }));
+        }
+        lines.iter().enumerate().for_each(|(row, line)| {
+            self.buffer.write_text(1, (lines.len() - row) as Int, line);
+        });
+    }
+
+    /// timings from earlier frames, drawn in the top left under the debug readout
+    /// lines that would land on labels or the hud move further down rather than go missing
+    pub fn render_profile(&mut self, profiler: &Profiler) {
+        let mut row = self.buffer.height - 3;
+        let header = format!("{:<16}{:>7} {:>7}", "pass", "last", "mean");
+        let quality = format!("{:<16}{:>7.2}", "quality", self.quality);
+        let lines = std::iter::once(header).chain(profiler.overlay())
+            .chain(std::iter::once(quality));
+        for line in lines {
+            while row >= 0 && !self.buffer.write_text(1, row, &line) {
+                row -= 1;
+            }
+            row -= 1;
+        }
     }
 
     pub fn render_planets(&mut self) {
@@ -41,6 +176,32 @@ impl<'d> Renderer<'d> {
         });
     }
 
+    /// every belt member is a single dim cell, depth tested against whatever is already drawn
+    pub fn render_belts(&mut self) {
+        let color = Color::cons(72, 66, 58);
+        self.system.belts.iter().for_each(|belt| {
+            (0..belt.len()).for_each(|idx| {
+                let viewframe = self.world_to_view(&belt.position(idx));
+                if viewframe.x <= 0.0 { return; }
+                let (screenx, screeny) = self.view_to_screen(&viewframe);
+                if let Some(idx) = self.buffer.inboundsdex(screenx, screeny) {
+                    if viewframe.x >= self.buffer.depth[idx] { return; }
+                    self.buffer.set(idx, Some(color), viewframe.x, None);
+                }
+            });
+        });
+    }
+
+    pub fn render_comets(&mut self) {
+        self.system.planets.iter().for_each(|planet| {
+            planet.features.iter().for_each(|feature| {
+                if let Feature::Comet(comet) = feature {
+                    self.render_comet(comet, planet);
+                }
+            });
+        });
+    }
+
     pub fn render_orbits(&mut self) {
         self.system.planets.iter().for_each(|planet| {
             planet.features.iter().for_each(|feature| {
@@ -51,6 +212,14 @@ impl<'d> Renderer<'d> {
         });
     }
 
+    pub fn render_trajectories(&mut self) {
+        self.system.planets.iter().for_each(|planet| {
+            if let Some(trajectory) = planet.trajectory() {
+                self.render_trajectory(trajectory, planet);
+            }
+        });
+    }
+
     pub fn render_spacerefs(&mut self) {
         self.system.planets.iter().for_each(|planet| {
             planet.features.iter().for_each(|feature| {
@@ -61,11 +230,186 @@ impl<'d> Renderer<'d> {
         });
     }
 
+    pub fn render_labels(&mut self) {
+        let mut bodies: Vec<(&Planet, Vec3)> = self.system.planets.iter()
+            .filter(|planet| planet.parent.is_none() || self.config.label_moons())
+            .map(|planet| (planet, self.world_to_view(&planet.loc)))
+            .collect();
+        // nearest bodies get first pick of the free cells
+        bodies.sort_by(|(a, _), (b, _)| {
+            self.distance_square(&a.loc).total_cmp(&self.distance_square(&b.loc))
+        });
+
+        bodies.iter().for_each(|(planet, viewframe)| {
+            if viewframe.x > 0.0 {
+                let (screenx, screeny) = self.view_to_screen(viewframe);
+                if let Some(idx) = self.buffer.inboundsdex(screenx, screeny) {
+                    if self.buffer.depth[idx] < viewframe.x - planet.rad * 1.5 { return; }
+                    self.place_label(planet, screenx, screeny);
+                    return;
+                }
+            }
+            self.place_edge_label(planet, viewframe);
+        });
+    }
+
+    /// l1 to l5 of every pair with masses as crosses named after the smaller body, placed after
+    /// the body labels so those keep their cells
+    pub fn render_lagrange(&mut self) {
+        lagrange_points(self.system).iter().for_each(|point| {
+            let viewframe = self.world_to_view(&point.loc);
+            if viewframe.x <= NEARPLANE { return; }
+            let (screenx, screeny) = self.view_to_screen(&viewframe);
+            let Some(idx) = self.buffer.inboundsdex(screenx, screeny) else { return; };
+            if self.buffer.depth[idx] < viewframe.x { return; }
+            if self.buffer.write_text(screenx, screeny, "+") {
+                let len = point.label.chars().count() as Int;
+                if !self.buffer.write_text(screenx + 2, screeny, &point.label) {
+                    self.buffer.write_text(screenx - len - 1, screeny, &point.label);
+                }
+            }
+        });
+    }
+
+    fn place_label(&mut self, planet: &Planet, screenx: Int, screeny: Int) {
+        let name = &planet.name;
+        let len = name.chars().count() as Int;
+        let candidates = [
+            (screenx + 2, screeny),
+            (screenx - len - 1, screeny),
+            (screenx - len / 2, screeny + 1),
+            (screenx - len / 2, screeny - 1),
+        ];
+        for (x, y) in candidates {
+            if self.buffer.write_text(x, y, name) { return; }
+        }
+    }
+
+    fn place_edge_label(&mut self, planet: &Planet, viewframe: &Vec3) {
+        let (halfwidth, halfheight) = (self.buffer.halfwidth() - 2, self.buffer.halfheight() - 1);
+        let mut dirx = viewframe.y * self.config.termcharaspect();
+        let mut diry = viewframe.z;
+        if dirx.abs() < Float::EPSILON && diry.abs() < Float::EPSILON {
+            diry = -1.0;
+        }
+        // a point behind the camera still says which way to turn, but not how far
+        if viewframe.x > 0.0 {
+            let (screenx, screeny) = self.view_to_screen(viewframe);
+            dirx = (screenx - self.buffer.halfwidth()) as Float;
+            diry = (screeny - self.buffer.halfheight()) as Float;
+        }
+
+        let scalex = halfwidth as Float / dirx.abs();
+        let scaley = halfheight as Float / diry.abs();
+        let scale = scalex.min(scaley);
+        let edgex = self.buffer.halfwidth() + (dirx * scale) as Int;
+        let edgey = self.buffer.halfheight() + (diry * scale) as Int;
+
+        let label = if scalex < scaley {
+            if dirx > 0.0 { format!("{} >", planet.name) } else { format!("< {}", planet.name) }
+        }
+        else if diry > 0.0 { format!("^ {}", planet.name) } else { format!("v {}", planet.name) };
+        let len = label.chars().count() as Int;
+        let x = (edgex - len / 2).min(self.buffer.viewwidth() - len).max(1);
+        let candidates = [edgey, edgey - 1, edgey + 1, edgey - 2, edgey + 2];
+        for y in candidates {
+            if self.buffer.write_text(x, y, &label) { return; }
+        }
+    }
+
+    pub fn render_panel(&mut self, hohmann: Option<&Hohmann>) {
+        let lines = match &self.viewmodel.target {
+            Some(target) => match self.system.planets.iter().find(|planet| &planet.name == target) {
+                Some(planet) => self.target_info(planet),
+                None => vec![format!("target: {}", target), "not in system".to_owned()],
+            },
+            None => vec!["no target".to_owned(), "t / T to cycle".to_owned()],
+        };
+        let lines = [lines, self.transfer_info(hohmann)].concat();
+        (0..self.buffer.height).for_each(|row| {
+            self.buffer.write_panel(row, "");
+        });
+        // the first row belongs to the debug readout
+        lines.iter().enumerate().for_each(|(row, line)| {
+            self.buffer.write_panel(row as Int + 2, line);
+        });
+    }
+
+    fn target_info(&self, planet: &Planet) -> Vec<String> {
+        let distance = self.distance_square(&planet.loc).sqrt() * DISTANCESCALE;
+        let angularsize = 2.0 * (planet.physrad / distance).atan();
+        let mut lines = vec![
+            format!("target: {}", planet.name),
+            String::new(),
+            format!("dist:   {:.4e} km", distance),
+            format!("        {:.4} au", distance / AU),
+            format!("radius: {:.0} km", planet.physrad),
+            format!("size:   {:.4}\u{b0}", angularsize.to_degrees()),
+        ];
+        // a lightsource has no phase of its own
+        if let Some(sun) = self.system.lightsources.first().filter(|_| !planet.lightsource) {
+            let mut tosun = *sun - planet.loc;
+            let mut toview = self.viewmodel.pos - planet.loc;
+            tosun.normalize();
+            toview.normalize();
+            let phase = tosun.inner_prod(&toview).clamp(-1.0, 1.0).acos();
+            lines.push(format!("phase:  {:.2}\u{b0}", phase.to_degrees()));
+        }
+        if let Some(orbit) = planet.path() {
+            let params = &orbit.params;
+            lines.push(String::new());
+            let axis = if params.is_open() { "q" } else { "a" };
+            lines.push(format!("{}:  {:.4e} km", axis, params.semimajor * DISTANCESCALE));
+            lines.push(format!("e:  {:.5}", params.eccentricity));
+            lines.push(format!("i:  {:.3}\u{b0}", params.inclination.to_degrees()));
+            lines.push(format!("\u{3a9}:  {:.3}\u{b0}", params.longitudeascnode.to_degrees()));
+            lines.push(format!("\u{3c9}:  {:.3}\u{b0}", params.argofperiapsis.to_degrees()));
+            lines.push(format!("\u{3bd}:  {:.3}\u{b0}", params.trueanomaly.to_degrees()));
+        }
+        lines
+    }
+
+    /// burns, flight time and launch window of the picked transfer, under the target
+    fn transfer_info(&self, hohmann: Option<&Hohmann>) -> Vec<String> {
+        let Some(transfer) = &self.system.transfer else { return Vec::new(); };
+        let Some(to) = &transfer.to else {
+            return vec![String::new(), format!("transfer: {}", transfer.from),
+                "pick where to".to_owned()];
+        };
+        let epoch = julian_day(SCENEEPOCH).unwrap();
+        let date = |seconds: f64| format_date(epoch + seconds / 86400.0);
+        let mut lines = vec![String::new(), format!("transfer: {} > {}", transfer.from, to)];
+        match hohmann {
+            Some(hohmann) => {
+                lines.push("hohmann".to_owned());
+                let burns = (hohmann.departure, hohmann.arrival);
+                lines.push(format!("dv:     {:.2} + {:.2} km/s", burns.0, burns.1));
+                lines.push(format!("time:   {:.1} d", hohmann.flight / 86400.0));
+                lines.push(format!("phase:  {:.1}\u{b0} now {:.1}\u{b0}",
+                    hohmann.phase.to_degrees(), hohmann.current.to_degrees()));
+                lines.push(format!("window: {:.1} d", hohmann.wait / 86400.0));
+                lines.push(format!("        {}", date(self.system.elapsed + hohmann.wait)));
+            }
+            None => lines.push("needs closed solar orbits".to_owned()),
+        }
+        match &transfer.lambert {
+            Some(Ok(lambert)) => {
+                lines.push(format!("lambert {}", date(lambert.date)));
+                let burns = (lambert.departure, lambert.arrival);
+                lines.push(format!("dv:     {:.2} + {:.2} km/s", burns.0, burns.1));
+                lines.push(format!("time:   {:.1} d", lambert.flight / 86400.0));
+            }
+            Some(Err(err)) => lines.push(err.clone()),
+            None => {}
+        }
+        lines
+    }
+
     fn render_ring(&mut self, ring: &Ring, planet: &Planet) {
         let distance = self.distance_square(&planet.loc).sqrt() - ring.rad;
         if self.behind_view(&planet.loc) || distance / ring.rad > 100.0 { return; }
-        let thetadelta = (distance / (ring.rad * 200.0)).max(0.01);
-        let gammadelta = (distance / (ring.depth * 10.0)).max(0.3);
+        let thetadelta = (distance / (ring.rad * 200.0)).max(0.01) / self.density();
+        let gammadelta = (distance / (ring.depth * 10.0)).max(0.3) / self.density();
         let thetastep = (TAU / thetadelta) as Int;
         let gammastep = (ring.depth / gammadelta) as Int;
 
@@ -94,44 +438,202 @@ impl<'d> Renderer<'d> {
         }
     }
 
+    /// draws the orbit as connected segments. each starting arc is halved until its chord lies
+    /// within half a cell of the projected curve, then clipped to the near plane and screen
     fn render_orbit(&mut self, orbit: &Orbit, planet: &Planet) {
         let distance = self.distance_square(&planet.loc).sqrt();
         if distance > self.config.orbital_distance() { return; }
-        let thetadelta = (distance / (orbit.params.semimajor * 90.0)).max(0.005);
-        let thetastep = (TAU / thetadelta) as Int;
+        let reach = (orbit.params.semimajor * 20.0).max(OPENREACH * AU / DISTANCESCALE);
+        self.render_arc(orbit, planet, orbit.params.anomaly_range(reach), None);
+    }
 
-        for thetamul in 0..thetastep {
-            let theta = thetamul as Float * thetadelta;
+    /// the stretch of an orbit between two true anomalies, lit like the planet's own orbit
+    /// unless it is given a color
+    fn render_arc(
+        &mut self, orbit: &Orbit, planet: &Planet, (first, last): (Float, Float),
+        tint: Option<Color>
+    ) {
+        let thetadelta = (last - first) / ORBITSEGMENTS as Float;
+        for segment in 0..ORBITSEGMENTS {
+            let start = first + segment as Float * thetadelta;
+            self.subdivide_orbit(orbit, planet, (start, start + thetadelta), 0, tint);
+        }
+    }
 
-            let mut orbit = *orbit;
-            orbit.params.trueanomaly = theta;
-            let worldframe = orbital_cartesian_transformation(&orbit);
-            
-            let viewframe = self.world_to_view(&worldframe);
-            if viewframe.x <= 0.0 { continue; }
-            let (screenx, screeny) = self.view_to_screen(&viewframe);
+    fn subdivide_orbit(
+        &mut self, orbit: &Orbit, planet: &Planet, (start, end): (Float, Float), depth: Int,
+        tint: Option<Color>
+    ) {
+        let middle = (start + end) / 2.0;
+        let worldframes = [start, middle, end].map(|theta| orbit_point(orbit, theta));
+        let viewframes = worldframes.map(|worldframe| self.world_to_view(&worldframe));
+        if viewframes.iter().all(|viewframe| viewframe.x < NEARPLANE) { return; }
 
-            if let Some(idx) = self.buffer.inboundsdex(screenx, screeny) {
-                if viewframe.x > self.buffer.depth[idx] { continue; }
-                let mut normal = worldframe - planet.loc;
-                normal.normalize();
-                let luminance = self.body_luminance(planet, worldframe, normal);
-                let mut color = Color::cons(204, 174, 6);
-                if orbit.apply_lighting {
-                    color.lighting(luminance);
-                    self.buffer.set(idx, Some(color), viewframe.x, None);
-                }
-                else {
-                    self.buffer.set(idx, None, viewframe.x, Some('.'));
-                }
+        if depth < ORBITDEPTH {
+            let split = if viewframes.iter().any(|viewframe| viewframe.x < NEARPLANE) {
+                // crossing the near plane, keep the curve tight where it passes the camera
+                true
+            }
+            else {
+                let [first, mid, last] = viewframes.map(|viewframe| self.project(&viewframe));
+                if self.offscreen_together(&[first, mid, last]) { return; }
+                let chord = ((first.0 + last.0) / 2.0 - mid.0, (first.1 + last.1) / 2.0 - mid.1);
+                chord.0.hypot(chord.1) > 0.5
+            };
+            if split {
+                self.subdivide_orbit(orbit, planet, (start, middle), depth + 1, tint);
+                self.subdivide_orbit(orbit, planet, (middle, end), depth + 1, tint);
+                return;
+            }
+        }
+
+        let (color, glyph) = if tint.is_some() {
+            (tint, None)
+        }
+        else if orbit.apply_lighting {
+            let mut normal = worldframes[1] - planet.loc;
+            normal.normalize();
+            let mut color = Color::cons(204, 174, 6);
+            color.lighting(self.body_luminance(planet, worldframes[1], normal));
+            (Some(color), None)
+        }
+        else {
+            (None, Some('.'))
+        };
+        self.draw_segment(viewframes[0], viewframes[2], color, glyph);
+    }
+
+    /// a recorded path as a polyline about its center, brighter where the craft has been. the
+    /// segment the craft is on is split at the marker
+    fn render_trajectory(&mut self, trajectory: &Trajectory, marker: &Planet) {
+        let origin = self.system.trajectory_origin(trajectory);
+        let (flown, ahead) = (Color::cons(80, 200, 220), Color::cons(30, 80, 90));
+        let current = trajectory.times.partition_point(|time| *time <= self.system.elapsed);
+        trajectory.points.windows(2).enumerate().for_each(|(idx, pair)| {
+            let from = self.world_to_view(&(origin + pair[0]));
+            let to = self.world_to_view(&(origin + pair[1]));
+            if idx + 1 == current {
+                let split = self.world_to_view(&marker.loc);
+                self.draw_segment(from, split, Some(flown), None);
+                self.draw_segment(split, to, Some(ahead), None);
+            }
+            else {
+                let color = if idx + 1 < current { flown } else { ahead };
+                self.draw_segment(from, to, Some(color), None);
             }
+        });
+    }
+
+    /// rasterizes a view space segment, clipped to the near plane and the screen. depth is
+    /// interpolated as 1 / x, which is linear across the screen
+    fn draw_segment(
+        &mut self, mut from: Vec3, mut to: Vec3, color: Option<Color>, glyph: Option<char>
+    ) {
+        if from.x < NEARPLANE && to.x < NEARPLANE { return; }
+        if from.x < NEARPLANE {
+            from = from + (to - from) * ((NEARPLANE - from.x) / (to.x - from.x));
+        }
+        if to.x < NEARPLANE {
+            to = to + (from - to) * ((NEARPLANE - to.x) / (from.x - to.x));
+        }
+
+        let (first, last) = (self.project(&from), self.project(&to));
+        let bounds = (self.buffer.viewwidth() as Float, self.buffer.height as Float);
+        let Some((tstart, tend)) = clip_segment(first, last, bounds) else { return; };
+        let (invfrom, invto) = (1.0 / from.x, 1.0 / to.x);
+        let span = (last.0 - first.0).abs().max((last.1 - first.1).abs()) * (tend - tstart);
+        let steps = span.ceil().max(1.0) as Int;
+
+        for step in 0..=steps {
+            let t = tstart + (tend - tstart) * step as Float / steps as Float;
+            let screenx = first.0 + (last.0 - first.0) * t;
+            let screeny = first.1 + (last.1 - first.1) * t;
+            let depth = 1.0 / (invfrom + (invto - invfrom) * t);
+
+            if let Some(idx) = self.buffer.inboundsdex(screenx as Int, screeny as Int) {
+                if depth > self.buffer.depth[idx] { continue; }
+                self.buffer.set(idx, color, depth, glyph);
+            }
+        }
+    }
+
+    /// true when every point is past the same edge of the screen, so nothing between them shows
+    fn offscreen_together(&self, points: &[(Float, Float)]) -> bool {
+        let (width, height) = (self.buffer.viewwidth() as Float, self.buffer.height as Float);
+        let past = |edge: fn(&(Float, Float), Float, Float) -> bool| {
+            points.iter().all(|point| edge(point, width, height))
+        };
+        past(|point, _, _| point.0 < 0.0) || past(|point, width, _| point.0 >= width)
+            || past(|point, _, _| point.1 < 0.0) || past(|point, _, height| point.1 >= height)
+    }
+
+    /// the coma and both tails as particles blended onto whatever is behind them. the ion tail
+    /// runs straight away from the nearest light, the dust tail bends back along the orbit.
+    /// particles are scattered the same way every frame so nothing shimmers
+    fn render_comet(&mut self, comet: &Comet, planet: &Planet) {
+        let nearest = self.system.lightsources.iter().map(|light| planet.loc - *light)
+            .min_by(|one, two| one.length().total_cmp(&two.length()));
+        let Some(mut away) = nearest else { return; };
+        let activity = Comet::activity(away.length());
+        if activity <= 0.0 { return; }
+        away.normalize();
+        let mut behind = planet.path()
+            .map(|path| orbital_velocity(&path.params, 1.0) * -1.0)
+            .unwrap_or(Vec3::cons(0, 0, 0));
+        if behind.length() > 0.0 {
+            behind.normalize();
+        }
+
+        let coma = comet.coma * activity.sqrt();
+        let tail = comet.tail * activity.sqrt();
+        let extent = self.screen_extent(planet.loc, planet.loc + away * tail).max(1.0);
+        let count = (extent * COMETPARTICLES * self.density()) as usize;
+        // fewer particles at low quality, each brighter so the total glow holds
+        let weight = 0.3 / (self.quality * self.quality);
+
+        let seed = planet.name.bytes()
+            .fold(0u64, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as u64));
+        let mut rng = Rng::seeded(seed);
+        let scatter = |rng: &mut Rng, spread: Float| {
+            Vec3::cons(rng.gauss(), rng.gauss(), rng.gauss()) * spread
+        };
+        (0..count.min(self.buffer.visual.len())).for_each(|_| {
+            let along = rng.uniform();
+            let ion = planet.loc + away * (along * tail) + scatter(&mut rng, coma * (0.2 + along));
+            self.blend_particle(ion, Color::cons(70, 120, 230), weight * (1.0 - along));
+
+            let along = rng.uniform();
+            let bend = away * along - behind * (along * along * 0.5);
+            let dust = planet.loc + bend * tail + scatter(&mut rng, coma * (0.3 + along * 2.0));
+            self.blend_particle(dust, Color::cons(210, 190, 140), weight * (1.0 - along));
+
+            let glow = planet.loc + scatter(&mut rng, coma * 0.5);
+            self.blend_particle(glow, Color::cons(140, 200, 180), weight * 0.2);
+        });
+    }
+
+    fn blend_particle(&mut self, worldframe: Vec3, mut color: Color, weight: Float) {
+        let viewframe = self.world_to_view(&worldframe);
+        if viewframe.x <= NEARPLANE { return; }
+        let (screenx, screeny) = self.view_to_screen(&viewframe);
+        if let Some(idx) = self.buffer.inboundsdex(screenx, screeny) {
+            color.lighting(weight);
+            self.buffer.blend(idx, color, viewframe.x);
         }
     }
 
+    /// how many samples apart two points land on screen, zero if either is behind the view
+    fn screen_extent(&self, one: Vec3, two: Vec3) -> Float {
+        let (one, two) = (self.world_to_view(&one), self.world_to_view(&two));
+        if one.x <= NEARPLANE || two.x <= NEARPLANE { return 0.0; }
+        let ((onex, oney), (twox, twoy)) = (self.project(&one), self.project(&two));
+        (twox - onex).hypot(twoy - oney)
+    }
+
     fn render_spaceref(&mut self, spaceref: &SpacialReference, planet: &Planet) {
         let distance = self.distance_square(&planet.loc).sqrt();
         if self.behind_view(&planet.loc) || distance > spaceref.length * 20.0 { return; }
-        let delta = 1.0 / 2.0;
+        let delta = 1.0 / 2.0 / self.buffer.density();
         let deltastep = (spaceref.length / delta) as Int;
         
         for deltamul in 0..deltastep {
@@ -165,7 +667,7 @@ impl<'d> Renderer<'d> {
     fn render_planet(&mut self, planet: &Planet) {
         if self.behind_view(&planet.loc) { return; }
         let distance = self.distance_square(&planet.loc).sqrt() - planet.rad;
-        let delta = (distance / (planet.rad * 200.0)).max(0.0075);
+        let delta = (distance / (planet.rad * 200.0)).max(0.0075) / self.density();
         let (thetadelta, phidelta) = (delta, delta * 2.0);
         let thetastep = (TAU / thetadelta) as Int;
         let phistep = (PI / phidelta) as Int;
@@ -222,20 +724,28 @@ impl<'d> Renderer<'d> {
         worldframe.rotatex(-params.tilt);
     }
 
+    /// samples per unit of the sampled passes, scaled up for supersampling and down by quality
+    fn density(&self) -> Float {
+        self.quality * self.buffer.density()
+    }
+
     fn world_to_view(&self, worldframe: &Vec3) -> Vec3 {
-        let mut viewframe = *worldframe - self.viewmodel.pos;
-        viewframe.rotatez(-self.viewmodel.rot);
-        viewframe.rotatey(self.viewmodel.tilt);
-        viewframe
+        self.viewmodel.orient.conjugate().rotate(*worldframe - self.viewmodel.pos)
     }
 
     fn view_to_screen(&self, viewframe: &Vec3) -> (Int, Int) {
+        let (screenx, screeny) = self.project(viewframe);
+        (screenx as Int, screeny as Int)
+    }
+
+    fn project(&self, viewframe: &Vec3) -> (Float, Float) {
         let invx = 1.0 / viewframe.x;
-        let (modx, mody) = (invx * self.config.fov() * self.config.termcharaspect(),
-            invx * self.config.fov());
-        let screenx = (viewframe.y * modx + self.buffer.halfwidth() as Float) as Int;
-        let screeny = (viewframe.z * mody + self.buffer.halfheight() as Float) as Int;
-        (screenx, screeny)
+        let (modx, mody) = (
+            invx * self.config.fov() * self.config.termcharaspect() * self.buffer.sampx as Float,
+            invx * self.config.fov() * self.buffer.sampy as Float,
+        );
+        (viewframe.y * modx + self.buffer.halfwidth() as Float,
+            viewframe.z * mody + self.buffer.halfheight() as Float)
     }
 
     fn behind_view(&self, point: &Vec3) -> bool {
@@ -266,6 +776,35 @@ impl<'d> Renderer<'d> {
     }
 }
 
+fn orbit_point(orbit: &Orbit, theta: Float) -> Vec3 {
+    let mut orbit = *orbit;
+    orbit.params.trueanomaly = theta;
+    orbital_cartesian_transformation(&orbit)
+}
+
+/// liang-barsky clip of a screen segment against the view, as the parameter range left inside
+fn clip_segment(
+    from: (Float, Float), to: (Float, Float), bounds: (Float, Float)
+) -> Option<(Float, Float)> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let edges = [(-dx, from.0), (dx, bounds.0 - from.0), (-dy, from.1), (dy, bounds.1 - from.1)];
+    let mut range: (Float, Float) = (0.0, 1.0);
+    for (direction, distance) in edges {
+        if direction == 0.0 {
+            if distance < 0.0 { return None; }
+            continue;
+        }
+        let t = distance / direction;
+        if direction < 0.0 {
+            range.0 = range.0.max(t);
+        }
+        else {
+            range.1 = range.1.min(t);
+        }
+    }
+    (range.0 <= range.1).then_some(range)
+}
+
 #[derive(Debug)]
 pub struct TextureData {
     pub height: usize, pub width: usize,
@@ -342,24 +881,47 @@ pub struct Buffer {
     visual: Vec<char>,
     color: Vec<Option<Color>>,
     depth: Vec<Float>,
+    overlay: Vec<Option<char>>,
+    reserved: Int,
+    sampx: Int, sampy: Int,
 }
 
 impl Buffer {
     pub fn cons(height: Int, width: Int) -> Buffer {
         let (wi, he) = (width as usize, height as usize);
-        debug_assert!(wi < 500 && he < 500);
+        debug_assert!(wi < 2000 && he < 2000);
         Buffer {
             height, width,
             visual: vec![' '; wi * he],
             color: vec![None; wi * he],
             depth: vec![Float::MAX; wi * he],
+            overlay: vec![None; wi * he],
+            reserved: 0,
+            sampx: 1, sampy: 1,
         }
     }
 
+    /// a buffer with sampx by sampy samples for every cell of a height by width terminal
+    pub fn supersampled(height: Int, width: Int, (sampx, sampy): (Int, Int)) -> Buffer {
+        let mut buffer = Buffer::cons(height * sampy, width * sampx);
+        (buffer.sampx, buffer.sampy) = (sampx, sampy);
+        buffer
+    }
+
+    /// keeps the rightmost columns out of the scene so panels can be drawn there. the scene
+    /// recenters on whatever width is left
+    pub fn reserve(&mut self, columns: Int) {
+        self.reserved = columns.clamp(0, self.width / 2);
+    }
+
+    pub const fn viewwidth(&self) -> Int {
+        self.width - self.reserved
+    }
+
     pub fn inboundsdex(&self, x: Int, y: Int) -> Option<usize> {
         let (height, width) = (self.height as usize, self.width as usize);
         let (x, y) = (x as usize, y as usize);
-        if x < width && y < height {
+        if x < self.viewwidth() as usize && y < height {
             let ytrans = height-1 - y;
             Some(ytrans * width + x)
         }
@@ -378,36 +940,190 @@ impl Buffer {
         }
     }
 
+    /// writes a line of the reserved panel, counting rows down from the top of the screen.
+    /// text that does not fit is cut off
+    pub fn write_panel(&mut self, row: Int, text: &str) {
+        if row < 0 || row >= self.height || self.reserved < 3 { return; }
+        let start = (row * self.width + self.viewwidth()) as usize;
+        self.overlay[start] = Some('|');
+        text.chars().take(self.reserved as usize - 2).enumerate().for_each(|(offset, chr)| {
+            self.overlay[start + 2 + offset] = Some(chr);
+        });
+    }
+
+    /// writes text onto the overlay layer, which is drawn over whatever the render passes left
+    /// in the cell. returns false without writing anything if any cell is taken or off-screen
+    pub fn write_text(&mut self, x: Int, y: Int, text: &str) -> bool {
+        let cells: Option<Vec<usize>> = (0..text.chars().count() as Int).map(|offset| {
+            // column zero is swallowed by the newline in display
+            if x + offset < 1 { return None; }
+            self.inboundsdex(x + offset, y).filter(|idx| self.overlay[*idx].is_none())
+        }).collect();
+        let Some(cells) = cells else { return false; };
+        cells.iter().zip(text.chars()).for_each(|(idx, chr)| {
+            self.overlay[*idx] = Some(chr);
+        });
+        true
+    }
+
+    /// box filters a supersampled buffer down onto this one. colour is averaged over every
+    /// sample so partly covered cells fade into the background, depth keeps the nearest sample
+    /// and the glyph comes from the nearest sample that has one
+    pub fn resolve(&mut self, samples: &Buffer) {
+        let (sampx, sampy) = (samples.sampx as usize, samples.sampy as usize);
+        let (width, samplewidth) = (self.width as usize, samples.width as usize);
+        let count = (sampx * sampy) as u32;
+        (0..self.visual.len()).for_each(|idx| {
+            let (row, col) = (idx / width, idx % width);
+            let (mut sum, mut covered) = ([0u32; 3], 0);
+            let (mut depth, mut glyph) = (Float::MAX, (Float::MAX, ' '));
+            let cell = (0..sampy).flat_map(|suby| (0..sampx).map(move |subx| (suby, subx)));
+            cell.for_each(|(suby, subx)| {
+                let sample = (row * sampy + suby) * samplewidth + col * sampx + subx;
+                if let Some(color) = samples.color[sample] {
+                    sum[0] += color.red as u32;
+                    sum[1] += color.green as u32;
+                    sum[2] += color.blue as u32;
+                    covered += 1;
+                }
+                depth = depth.min(samples.depth[sample]);
+                if samples.visual[sample] != ' ' && samples.depth[sample] < glyph.0 {
+                    glyph = (samples.depth[sample], samples.visual[sample]);
+                }
+            });
+            if covered > 0 {
+                let [red, green, blue] = sum.map(|channel| (channel / count) as u8);
+                self.color[idx] = Some(Color::cons(red, green, blue));
+            }
+            self.depth[idx] = depth;
+            self.visual[idx] = glyph.1;
+        });
+    }
+
+    /// samples along the longer side of a cell, the factor sample density has to rise by
+    pub fn density(&self) -> Float {
+        self.sampx.max(self.sampy) as Float
+    }
+
+    /// adds light onto a cell without claiming its depth, so glows stay see-through. hidden
+    /// behind anything nearer, and channels saturate at white
+    pub fn blend(&mut self, idx: usize, color: Color, depth: Float) {
+        if depth > self.depth[idx] { return; }
+        let base = self.color[idx].unwrap_or(Color::cons(0, 0, 0));
+        self.color[idx] = Some(Color::cons(base.red.saturating_add(color.red),
+            base.green.saturating_add(color.green), base.blue.saturating_add(color.blue)));
+    }
+
+    pub fn cell(&self, idx: usize) -> (char, Option<Color>) {
+        (self.overlay[idx].unwrap_or(self.visual[idx]), self.color[idx])
+    }
+
+    /// the cell as the render passes left it, without any text over it
+    pub fn scene_cell(&self, idx: usize) -> (char, Option<Color>) {
+        (self.visual[idx], self.color[idx])
+    }
+
     pub fn display(&self) {
-        let mut string = String::new();
         print!("\x1b[H");
+        print!("{}", self.to_ansi());
+        stdout().flush().unwrap();
+    }
+
+    pub fn to_ansi(&self) -> String {
+        let mut string = String::new();
         self.visual.iter().enumerate().for_each(|(idx, ele)| {
             if let Some(color) = self.color[idx] {
                 string.push_str(&color.to_ansiback());
             }
             if idx % self.width as usize != 0 {
-                string.push(*ele);
+                string.push(self.overlay[idx].unwrap_or(*ele));
             }
             else {
                 string.push('\n')
             }
             string.push_str("\x1b[0m");
         });
-        print!("{}", string);
-        stdout().flush().unwrap();
+        string
     }
 
+    // rounded to whole cells so samples land in the cell the plain buffer would have used
     const fn halfheight(&self) -> Int {
-        self.height / 2
+        self.height / self.sampy / 2 * self.sampy
     }
 
     const fn halfwidth(&self) -> Int {
-        self.width / 2
+        self.viewwidth() / self.sampx / 2 * self.sampx
     }
 
     pub fn clear(&mut self) {
         self.visual.fill(' ');
         self.color.fill(None);
         self.depth.fill(Float::MAX);
+        self.overlay.fill(None);
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn edge_label_wider_than_view() {
+        let viewmodel = ViewModel::new(Vec3::cons(0, 0, 0));
+        let system = System::from(Planet::cons("sun".to_owned(), Vec3::cons(0, 0, 0), 1.0,
+            None, true, None));
+        let config = Config::default();
+        let mut buffer = Buffer::cons(10, 6);
+        let planet = Planet::cons("neptune".to_owned(), Vec3::cons(-10, 5, 0), 1.0,
+            None, false, None);
+        let mut renderer = Renderer::cons(&viewmodel, &mut buffer, &system, &config);
+        renderer.place_edge_label(&planet, &Vec3::cons(-10, 5, 0));
+        assert!(buffer.overlay.iter().all(|cell| cell.is_none()), "label should not fit");
+    }
+
+    #[test]
+    fn profile_moves_past_overlay() {
+        let viewmodel = ViewModel::new(Vec3::cons(0, 0, 0));
+        let system = System::from(Planet::cons("sun".to_owned(), Vec3::cons(0, 0, 0), 1.0,
+            None, true, None));
+        let config = Config::default();
+        let mut buffer = Buffer::cons(8, 40);
+        let mut profiler = Profiler::new();
+        profiler.record("render_planets", std::time::Duration::from_millis(2));
+        // a label across the row the header would take
+        buffer.write_text(1, 5, "mars");
+        Renderer::cons(&viewmodel, &mut buffer, &system, &config).render_profile(&profiler);
+        let text: String = buffer.overlay.iter().map(|cell| cell.unwrap_or(' ')).collect();
+        ["pass", "render_planets", "quality"].iter().for_each(|name| {
+            assert!(text.contains(name), "{} missing", name);
+        });
+    }
+
+    #[test]
+    fn sun_has_no_phase() {
+        let viewmodel = ViewModel::new(Vec3::cons(0, -50, 10));
+        let system = System::from(Planet::cons("sun".to_owned(), Vec3::cons(0, 0, 0), 1.0,
+            None, true, None));
+        let config = Config::default();
+        let mut buffer = Buffer::cons(10, 40);
+        let renderer = Renderer::cons(&viewmodel, &mut buffer, &system, &config);
+        let lines = renderer.target_info(&system.planets[0]);
+        assert!(lines.iter().all(|line| !line.starts_with("phase")), "{:?}", lines);
+        assert!(lines.iter().all(|line| !line.contains("NaN")), "{:?}", lines);
+    }
+
+    #[test]
+    fn blend_saturates() {
+        let mut buffer = Buffer::cons(1, 2);
+        buffer.set(1, None, 1.0, None);
+        (0..3).for_each(|_| {
+            buffer.blend(0, Color::cons(200, 100, 0), 2.0);
+            buffer.blend(1, Color::cons(200, 100, 0), 2.0);
+        });
+        let color = buffer.color[0].unwrap();
+        assert!((color.red, color.green, color.blue) == (255, 255, 0), "{:?}", color);
+        assert!(buffer.color[1].is_none(), "hidden glow was blended");
     }
 }
@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::controls::{parse_key, Action, Keybindings};
+use crate::Float;
+
+
+
+/// a scripted run of actions. each line is `<time> <action>` or `<start>-<end> <action>`, with
+/// times in seconds from the start of the script. a range repeats the action every step, the
+/// way a held key would. actions are named as in the keybindings section, or given as
+/// `key <char>` to go through the active bindings
+pub struct Replay {
+    events: Vec<ReplayEvent>,
+    time: Float,
+}
+
+struct ReplayEvent {
+    start: Float,
+    end: Option<Float>,
+    action: Action,
+}
+
+impl Replay {
+    pub fn load(path: &str, keybindings: &Keybindings) -> Result<Replay, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut events = Vec::new();
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?.trim().to_string();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let event = parse_event(&line, keybindings)
+                .map_err(|err| format!("{} line {}: {}", path, number + 1, err))?;
+            events.push(event);
+        }
+        events.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Ok(Replay { events, time: 0.0 })
+    }
+
+    /// the actions that fall in the next fixed step of `timestep` seconds
+    pub fn step(&mut self, timestep: Float) -> Vec<Action> {
+        let (from, to) = (self.time, self.time + timestep);
+        self.time = to;
+        self.events.iter().filter(|event| {
+            match event.end {
+                Some(end) => event.start < to && end >= from,
+                None => event.start >= from && event.start < to,
+            }
+        }).map(|event| event.action.clone()).collect()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.events.iter().all(|event| event.end.unwrap_or(event.start) < self.time)
+    }
+
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+    }
+}
+
+fn parse_event(line: &str, keybindings: &Keybindings) -> Result<ReplayEvent, Box<dyn Error>> {
+    let mut tokens = line.split_whitespace();
+    let time = tokens.next().ok_or("missing time")?;
+    let name = tokens.next().ok_or("missing action")?;
+    let (start, end) = match time.split_once('-') {
+        Some((start, end)) => (start.parse::<Float>()?, Some(end.parse::<Float>()?)),
+        None => (time.parse::<Float>()?, None),
+    };
+
+    let action = if name == "key" {
+        let key = parse_key(tokens.next().ok_or("key needs a character")?)?;
+        keybindings.resolve(key).cloned().ok_or(format!("{} is not bound", key))?
+    }
+    else {
+        Action::parse(name)?
+    };
+    Ok(ReplayEvent { start, end, action })
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn events() {
+        let keybindings = Keybindings::default();
+        let event = parse_event("1.5 translate:forward", &keybindings).unwrap();
+        assert!(event.start == 1.5 && event.end.is_none() && event.action == Action::Forward);
+        let event = parse_event("2-4.5 key w", &keybindings).unwrap();
+        assert!(event.start == 2.0 && event.end == Some(4.5) && event.action == Action::Forward);
+        assert!(parse_event("0 key space", &keybindings).is_ok());
+    }
+
+    #[test]
+    fn bad_events() {
+        let keybindings = Keybindings::default();
+        assert!(parse_event("1", &keybindings).is_err());
+        assert!(parse_event("soon quit", &keybindings).is_err());
+        assert!(parse_event("1 key", &keybindings).is_err());
+        assert!(parse_event("1 key wx", &keybindings).is_err());
+        assert!(parse_event("1 fly:sideways", &keybindings).is_err());
+    }
+}
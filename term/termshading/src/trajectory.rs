@@ -0,0 +1,175 @@
+use std::error::Error;
+use std::fs::read_to_string;
+
+use crate::math::Vec3;
+use crate::utils::julian_day;
+use crate::{Float, AU, SCENEEPOCH};
+
+
+
+/// obliquity of the ecliptic at j2000, the tilt between the equatorial and ecliptic frames
+const OBLIQUITY: Float = 23.439281;
+/// radius in km of the body that stands in for the craft, so it can be labelled and targeted
+pub const MARKERRADIUS: Float = 2000.0;
+
+
+
+/// the frame a file's vectors are given in. ecliptic and equatorial are the j2000 frames
+/// horizons and spice export, the scene frame is the ecliptic mirrored in x
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frame {
+    Scene,
+    Ecliptic,
+    Equatorial,
+}
+
+impl Frame {
+    pub fn parse(name: &str) -> Option<Frame> {
+        match name {
+            "scene" => Some(Frame::Scene),
+            "ecliptic" => Some(Frame::Ecliptic),
+            "equatorial" => Some(Frame::Equatorial),
+            _ => None,
+        }
+    }
+
+    fn to_scene(self, mut vec: Vec3) -> Vec3 {
+        if self == Frame::Equatorial {
+            vec.rotatex(-OBLIQUITY.to_radians());
+        }
+        if self != Frame::Scene {
+            vec.reflx();
+        }
+        vec
+    }
+}
+
+/// km per length unit and seconds per time unit of velocities, for `units=km`, `m` or `au`.
+/// au velocities are per day as horizons gives them, the others per second
+pub fn units(name: &str) -> Option<(Float, Float)> {
+    match name {
+        "km" => Some((1.0, 1.0)),
+        "m" => Some((1e-3, 1.0)),
+        "au" => Some((AU, 86400.0)),
+        _ => None,
+    }
+}
+
+/// a recorded path, in km and km/s until transform_mini scales it like everything else. times
+/// are seconds since the scene epoch
+#[derive(Debug)]
+pub struct Trajectory {
+    pub center: Option<String>,
+    pub times: Vec<f64>,
+    pub points: Vec<Vec3>,
+    /// empty when the file only has positions
+    pub velocities: Vec<Vec3>,
+}
+
+impl Trajectory {
+    pub fn load(
+        path: &str, frame: Frame, units: (Float, Float)
+    ) -> Result<Trajectory, Box<dyn Error>> {
+        let text = read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        Trajectory::parse(&text, frame, units)
+    }
+
+    /// rows of `time,x,y,z` or `time,x,y,z,vx,vy,vz`, the time as a julian day or YYYY-MM-DD.
+    /// blank lines, `#` comments and a header row are skipped, and rows must be in time order
+    pub fn parse(
+        text: &str, frame: Frame, (length, timeunit): (Float, Float)
+    ) -> Result<Trajectory, Box<dyn Error>> {
+        let scene = julian_day(SCENEEPOCH)?;
+        let mut trajectory = Trajectory {
+            center: None, times: Vec::new(), points: Vec::new(), velocities: Vec::new(),
+        };
+        let rows = text.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim().starts_with('#'));
+        for (count, (number, line)) in rows.enumerate() {
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+            let Ok(julianday) = julian_day(fields[0]) else {
+                if count == 0 { continue; }
+                return Err(format!("line {}: bad time {}", number + 1, fields[0]).into());
+            };
+            let values = fields[1..].iter().map(|field| field.parse::<Float>())
+                .collect::<Result<Vec<Float>, _>>()
+                .map_err(|err| format!("line {}: {}", number + 1, err))?;
+            if values.len() != 3 && values.len() != 6 {
+                return Err(format!("line {}: expected 3 or 6 values", number + 1).into());
+            }
+            let time = (julianday - scene) * 86400.0;
+            if trajectory.times.last().is_some_and(|last| time <= *last) {
+                return Err(format!("line {}: times must increase", number + 1).into());
+            }
+            trajectory.times.push(time);
+            let point = Vec3::cons(values[0], values[1], values[2]) * length;
+            trajectory.points.push(frame.to_scene(point));
+            if values.len() == 6 {
+                let velocity = Vec3::cons(values[3], values[4], values[5]) * (length / timeunit);
+                trajectory.velocities.push(frame.to_scene(velocity));
+            }
+        }
+        if trajectory.points.is_empty() {
+            return Err("trajectory has no rows".into());
+        }
+        if trajectory.velocities.len() != trajectory.points.len() {
+            trajectory.velocities.clear();
+        }
+        Ok(trajectory)
+    }
+
+    pub fn scale(&mut self, factor: Float) {
+        self.points.iter_mut().for_each(|point| *point /= factor);
+        self.velocities.iter_mut().for_each(|velocity| *velocity /= factor);
+    }
+
+    /// where the craft is at a time, relative to its center. hermite interpolated when there
+    /// are velocities and linear otherwise, and held at the ends outside the recording
+    pub fn position(&self, time: f64) -> Vec3 {
+        let next = self.times.partition_point(|sample| *sample <= time);
+        if next == 0 { return self.points[0]; }
+        if next == self.times.len() { return self.points[next - 1]; }
+        let (before, after) = (next - 1, next);
+        let span = self.times[after] - self.times[before];
+        let s = ((time - self.times[before]) / span) as Float;
+        let span = span as Float;
+        let (from, to) = (self.points[before], self.points[after]);
+        if self.velocities.is_empty() {
+            return from + (to - from) * s;
+        }
+        let (s2, s3) = (s * s, s * s * s);
+        from * (2.0 * s3 - 3.0 * s2 + 1.0)
+            + self.velocities[before] * ((s3 - 2.0 * s2 + s) * span)
+            + to * (3.0 * s2 - 2.0 * s3)
+            + self.velocities[after] * ((s3 - s2) * span)
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn equatorial_hermite() {
+        // a circle in the equatorial plane at 1 km/s, sampled every eighth of a turn
+        let (radius, speed) = (1000.0, 1.0);
+        let eighth = (radius / speed) * std::f32::consts::FRAC_PI_4;
+        let text = (0..9).map(|step| {
+            let angle = step as Float * std::f32::consts::FRAC_PI_4;
+            let (sin, cos) = angle.sin_cos();
+            format!("{},{},{},0,{},{},0", 2460676.5 + step as f64 * eighth as f64 / 86400.0,
+                radius * cos, radius * sin, -speed * sin, speed * cos)
+        }).collect::<Vec<String>>().join("\n");
+        let text = format!("time,x,y,z,vx,vy,vz\n{}", text);
+        let trajectory = Trajectory::parse(&text, Frame::Equatorial, (1.0, 1.0)).unwrap();
+
+        let pos = trajectory.position(eighth as f64 * 1.5);
+        assert!((pos.length() - radius).abs() < radius * 0.01, "radius {}", pos.length());
+        // the equatorial y axis dips below the ecliptic, and the scene mirrors x
+        let tilt = pos.z.atan2(pos.y).to_degrees();
+        assert!((tilt + OBLIQUITY).abs() < 0.1, "tilt {}", tilt);
+        assert!(pos.x < 0.0, "mirrored x {}", pos.x);
+    }
+}
@@ -0,0 +1,252 @@
+use std::f64::consts::{PI, TAU};
+
+use crate::entities::{Orbit, OrbitalParams, System};
+use crate::math::{orbital_cartesian_transformation, state_to_elements, Vec3};
+use crate::physics::{cross, dot, norm, scale, state_vectors, sub, to_state, to_vec, State};
+use crate::{Float, DISTANCESCALE, SUNGM};
+
+
+
+/// bisection steps for the lambert solver, plenty for f64
+const LAMBERTSTEPS: usize = 200;
+
+
+
+/// a trip planned between two bodies on paths about the sun. the hohmann figures are worked out
+/// afresh as the bodies move, a lambert solution stays fixed to the date it was asked for
+#[derive(Debug)]
+pub struct Transfer {
+    pub from: String,
+    pub to: Option<String>,
+    pub lambert: Option<Result<Lambert, String>>,
+}
+
+/// the two burn ellipse between circular coplanar orbits at the bodies' mean distances
+#[derive(Debug)]
+pub struct Hohmann {
+    /// km/s at each end, relative to the body left and the body reached
+    pub departure: f64,
+    pub arrival: f64,
+    /// seconds
+    pub flight: f64,
+    /// how far the arrival body should lead the departure body when leaving, and leads now
+    pub phase: f64,
+    pub current: f64,
+    /// seconds until the phase next comes round
+    pub wait: f64,
+    /// the ellipse in scene units with the departure point at its current anomaly, and the
+    /// anomalies flown through
+    pub path: Orbit,
+    pub sweep: (Float, Float),
+}
+
+/// the conic joining where the departure body is on a date to where the arrival body is a
+/// flight time later
+#[derive(Debug)]
+pub struct Lambert {
+    /// seconds since the scene epoch
+    pub date: f64,
+    pub flight: f64,
+    pub departure: f64,
+    pub arrival: f64,
+    pub path: Orbit,
+    pub sweep: (Float, Float),
+}
+
+impl Transfer {
+    pub fn cons(from: &str) -> Transfer {
+        Transfer { from: from.to_owned(), to: None, lambert: None }
+    }
+
+    pub fn hohmann(&self, system: &System) -> Option<Hohmann> {
+        let to = self.to.as_deref()?;
+        let (one, two) = (heliocentric(system, &self.from)?, heliocentric(system, to)?);
+        let sun = sun_loc(system);
+        let (leaving, reaching) = (mean_distance(&one), mean_distance(&two));
+        let (departure, arrival, flight, phase) = hohmann(leaving, reaching, SUNGM as f64);
+
+        // the scene is mirrored in x, so prograde runs clockwise seen from above
+        let longitude = |path: &Orbit| {
+            let relative = orbital_cartesian_transformation(path) - sun;
+            relative.y.atan2(relative.x) as f64
+        };
+        let current = (longitude(&one) - longitude(&two)).rem_euclid(TAU);
+        let motion = |distance: f64| (SUNGM as f64 / distance.powi(3)).sqrt();
+        let rate = motion(reaching) - motion(leaving);
+        let wait = ((phase - current) / rate).rem_euclid(TAU / rate.abs());
+
+        let mut outward = orbital_cartesian_transformation(&one) - sun;
+        outward.z = 0.0;
+        outward.normalize();
+        let prograde = Vec3::cons(outward.y, -outward.x, 0.0);
+        let speed = (SUNGM as f64 * (2.0 / leaving - 2.0 / (leaving + reaching))).sqrt();
+        let params =
+            state_to_elements(outward * leaving as Float, prograde * speed as Float, SUNGM);
+        let start = params.trueanomaly;
+        Some(Hohmann {
+            departure, arrival, flight, phase, current, wait,
+            path: scene_path(params, sun), sweep: (start, start + PI as Float),
+        })
+    }
+
+    /// solves for leaving at `date` seconds since the scene epoch and arriving `flight` seconds
+    /// later, going the short way round if that is prograde and the long way otherwise
+    pub fn plan_lambert(&mut self, system: &System, date: f64, flight: f64) {
+        let Some(to) = self.to.as_deref() else { return; };
+        let bodies = heliocentric(system, &self.from).zip(heliocentric(system, to));
+        let Some((mut one, mut two)) = bodies else {
+            self.lambert = Some(Err("no paths to plan on".to_owned()));
+            return;
+        };
+        let offset = date - system.elapsed;
+        one.advance(offset as Float);
+        two.advance((offset + flight) as Float);
+        let sun = to_state(sun_loc(system) * DISTANCESCALE);
+        let ((start, startvel), (end, endvel)) =
+            (state_vectors(&one, SUNGM as f64), state_vectors(&two, SUNGM as f64));
+        let (start, end) = (sub(start, sun), sub(end, sun));
+
+        self.lambert = Some(lambert(start, end, flight, SUNGM as f64).map(|(leave, reach)| {
+            let params = state_to_elements(to_vec(start), to_vec(leave), SUNGM);
+            let first = params.trueanomaly;
+            Lambert {
+                date, flight,
+                departure: norm(sub(leave, startvel)), arrival: norm(sub(endvel, reach)),
+                path: scene_path(params, sun_loc(system)),
+                sweep: (first, first + sweep(start, end) as Float),
+            }
+        }).ok_or_else(|| "no lambert solution".to_owned()));
+    }
+}
+
+/// the path of a body that goes round the sun itself rather than a planet, if it is closed
+fn heliocentric(system: &System, name: &str) -> Option<Orbit> {
+    let body = system.find(name).filter(|body| body.parent.is_none())?;
+    body.path().filter(|path| !path.params.is_open()).copied()
+}
+
+fn sun_loc(system: &System) -> Vec3 {
+    system.find("sun").map_or(Vec3::cons(0, 0, 0), |sun| sun.loc)
+}
+
+/// km
+fn mean_distance(path: &Orbit) -> f64 {
+    (path.params.semimajor * DISTANCESCALE) as f64
+}
+
+/// elements in km made into a drawable path about the sun in scene units
+fn scene_path(params: OrbitalParams, sun: Vec3) -> Orbit {
+    let mut path = Orbit::cons(params, sun, false);
+    path.params.semimajor /= DISTANCESCALE;
+    path
+}
+
+/// burns, flight time and lead angle for a hohmann transfer between circular orbits of radius
+/// `leaving` and `reaching` km, either way out or in
+pub fn hohmann(leaving: f64, reaching: f64, gm: f64) -> (f64, f64, f64, f64) {
+    let semimajor = (leaving + reaching) / 2.0;
+    let departure = ((gm / leaving).sqrt() * ((reaching / semimajor).sqrt() - 1.0)).abs();
+    let arrival = ((gm / reaching).sqrt() * (1.0 - (leaving / semimajor).sqrt())).abs();
+    let flight = PI * (semimajor.powi(3) / gm).sqrt();
+    let phase = (PI - (gm / reaching.powi(3)).sqrt() * flight).rem_euclid(TAU);
+    (departure, arrival, flight, phase)
+}
+
+/// angle swept going prograde from one position to another. prograde motion has its angular
+/// momentum along -z in the mirrored scene frame
+fn sweep(start: State, end: State) -> f64 {
+    let angle = (dot(start, end) / (norm(start) * norm(end))).clamp(-1.0, 1.0).acos();
+    if cross(start, end)[2] > 0.0 { TAU - angle } else { angle }
+}
+
+/// universal variable lambert solver after curtis, giving the velocities at both ends of the
+/// single revolution prograde conic through two positions `flight` seconds apart
+pub fn lambert(start: State, end: State, flight: f64, gm: f64) -> Option<(State, State)> {
+    let (one, two) = (norm(start), norm(end));
+    let angle = sweep(start, end);
+    let shape = angle.sin() * (one * two / (1.0 - angle.cos())).sqrt();
+    if !shape.is_finite() || shape.abs() < 1e-9 * (one + two) || flight <= 0.0 { return None; }
+
+    let radial = |z: f64| one + two + shape * (z * stumpff_s(z) - 1.0) / stumpff_c(z).sqrt();
+    let time = |z: f64| {
+        let y = radial(z);
+        ((y / stumpff_c(z)).powf(1.5) * stumpff_s(z) + shape * y.sqrt()) / gm.sqrt()
+    };
+    // time of flight grows with z wherever y is positive, and passes every value below the
+    // first full ellipse
+    let mut high = TAU * TAU - 1e-9;
+    let mut low = -TAU * TAU;
+    while radial(low) > 0.0 && time(low) > flight {
+        low *= 2.0;
+        if low < -1e6 { return None; }
+    }
+    if time(high) < flight { return None; }
+    (0..LAMBERTSTEPS).for_each(|_| {
+        let middle = (low + high) / 2.0;
+        if radial(middle) < 0.0 || time(middle) < flight { low = middle; } else { high = middle; }
+    });
+
+    let y = radial((low + high) / 2.0);
+    let f = 1.0 - y / one;
+    let g = shape * (y / gm).sqrt();
+    let gdot = 1.0 - y / two;
+    let leave = scale(sub(end, scale(start, f)), 1.0 / g);
+    let reach = scale(sub(scale(end, gdot), start), 1.0 / g);
+    Some((leave, reach))
+}
+
+fn stumpff_s(z: f64) -> f64 {
+    if z > 1e-6 {
+        let root = z.sqrt();
+        (root - root.sin()) / root.powi(3)
+    }
+    else if z < -1e-6 {
+        let root = (-z).sqrt();
+        (root.sinh() - root) / root.powi(3)
+    }
+    else {
+        1.0 / 6.0 - z / 120.0
+    }
+}
+
+fn stumpff_c(z: f64) -> f64 {
+    if z > 1e-6 {
+        (1.0 - z.sqrt().cos()) / z
+    }
+    else if z < -1e-6 {
+        ((-z).sqrt().cosh() - 1.0) / -z
+    }
+    else {
+        0.5 - z / 24.0
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SUN: f64 = 1.3271244e11;
+
+    #[test]
+    fn earth_to_mars() {
+        let (departure, arrival, flight, phase) = hohmann(1.496e8, 2.279e8, SUN);
+        assert!((departure - 2.94).abs() < 0.01, "departure {}", departure);
+        assert!((arrival - 2.65).abs() < 0.01, "arrival {}", arrival);
+        assert!((flight / 86400.0 - 259.0).abs() < 1.0, "flight {}", flight / 86400.0);
+        assert!((phase.to_degrees() - 44.3).abs() < 0.5, "phase {}", phase.to_degrees());
+    }
+
+    #[test]
+    fn quarter_circle() {
+        // prograde in the mirrored scene frame carries +x round to -y
+        let radius = 1.496e8;
+        let speed = (SUN / radius).sqrt();
+        let quarter = TAU * (radius.powi(3) / SUN).sqrt() / 4.0;
+        let (leave, reach) =
+            lambert([radius, 0.0, 0.0], [0.0, -radius, 0.0], quarter, SUN).unwrap();
+        assert!(norm(sub(leave, [0.0, -speed, 0.0])) / speed < 1e-6, "leave {:?}", leave);
+        assert!(norm(sub(reach, [-speed, 0.0, 0.0])) / speed < 1e-6, "reach {:?}", reach);
+    }
+}
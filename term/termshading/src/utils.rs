@@ -5,61 +5,60 @@ use std::time::Duration;
 use std::io::{stdout, Write};
 use std::error::Error;
 
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, MouseEventKind};
 
-use crate::ViewModel;
+use crate::controls::{Action, Keybindings};
+use crate::entities::Clock;
+use crate::{Int, System, ViewModel};
 
 
 
-pub fn print_debug(viewmodel: &ViewModel) {
+/// julian day of 1970-01-01, where parse_date counts from
+const UNIXJULIANDAY: f64 = 2440587.5;
+
+
+
+pub fn print_debug(viewmodel: &ViewModel, clock: &Clock, system: &System) {
     print!("\x1b[H");
-    print!("x: {:.2}, y: {:.2}, z: {:.2}, rot: {:.2}, tilt: {:.2}, speed: {:.2}",
-        viewmodel.pos.x, viewmodel.pos.y, viewmodel.pos.z,
-        viewmodel.rot, viewmodel.tilt, viewmodel.transspeed
-    );
+    print!("{}", debug_line(viewmodel, clock, system));
     stdout().flush().unwrap();
 }
 
+pub fn debug_line(viewmodel: &ViewModel, clock: &Clock, system: &System) -> String {
+    let dynamics = match &system.physics {
+        Some(physics) => format!(", nbody drift: {:.1e}", physics.drift()),
+        None => String::new(),
+    };
+    format!("x: {:.2}, y: {:.2}, z: {:.2}, yaw: {:.2}, pitch: {:.2}, roll: {:.2}, speed: {:.2}, \
+        camera: {}, warp: {}x{}, days: {:.2}{}",
+        viewmodel.pos.x, viewmodel.pos.y, viewmodel.pos.z,
+        viewmodel.yaw(), viewmodel.pitch(), viewmodel.bank(), viewmodel.transspeed,
+        if viewmodel.flying() { "flying" } else { viewmodel.mode.name() },
+        clock.warp, if clock.paused { " (paused)" } else { "" }, system.elapsed / 86400.0, dynamics
+    )
+}
+
 pub fn sleep(time: u64) {
     std::thread::sleep(std::time::Duration::from_millis(time));
 }
 
-pub fn get_user_input() -> Vec<char> {
+pub fn get_user_input(keybindings: &Keybindings) -> Vec<Action> {
     let mut inputs = Vec::new();
     if let Ok(true) = event::poll(Duration::from_millis(1)) {
-        if let Ok(Event::Key(key_event)) = event::read() {
-            match key_event.code {
-                KeyCode::Char('w') => inputs.push('w'),
-                KeyCode::Char('s') => inputs.push('s'),
-                KeyCode::Char('W') => inputs.push('W'),
-                KeyCode::Char('S') => inputs.push('S'),
-                KeyCode::Char('a') => inputs.push('a'),
-                KeyCode::Char('d') => inputs.push('d'),
-                KeyCode::Char('q') => inputs.push('q'),
-                KeyCode::Char('e') => inputs.push('e'),
-                KeyCode::Char('p') => inputs.push('p'),
-                KeyCode::Char('f') => inputs.push('f'),
-                KeyCode::Char('r') => inputs.push('r'),
-                KeyCode::Char('t') => inputs.push('t'),
-                KeyCode::Char('g') => inputs.push('g'),
-                KeyCode::Char('[') => inputs.push('['),
-                KeyCode::Char(']') => inputs.push(']'),
-                KeyCode::Char('n') => inputs.push('n'),
-                KeyCode::Char('m') => inputs.push('m'),
-                KeyCode::Char('1') => inputs.push('1'),
-                KeyCode::Char('2') => inputs.push('2'),
-                KeyCode::Char('3') => inputs.push('3'),
-                KeyCode::Char('4') => inputs.push('4'),
-                KeyCode::Char('5') => inputs.push('5'),
-                KeyCode::Char('6') => inputs.push('6'),
-                KeyCode::Char('7') => inputs.push('7'),
-                KeyCode::Char('8') => inputs.push('8'),
-                KeyCode::Char('9') => inputs.push('9'),
-                KeyCode::Char('0') => inputs.push('0'),
-                KeyCode::Char(',') => inputs.push(','),
-                KeyCode::Char('.') => inputs.push('.'),
-                _ => {}
+        match event::read() {
+            Ok(Event::Key(key_event)) => {
+                if let KeyCode::Char(key) = key_event.code {
+                    if let Some(action) = keybindings.resolve(key) {
+                        inputs.push(action.clone());
+                    }
+                }
             }
+            Ok(Event::Mouse(mouse_event)) => match mouse_event.kind {
+                MouseEventKind::ScrollUp => inputs.push(Action::ZoomIn),
+                MouseEventKind::ScrollDown => inputs.push(Action::ZoomOut),
+                _ => {}
+            },
+            _ => {}
         }
     }
     inputs
@@ -70,5 +69,86 @@ pub fn flash_error(error: Box<dyn Error>, time: u64) {
     sleep(time);
 }
 
+/// days since 1970-01-01 for a `YYYY-MM-DD` date on the proleptic gregorian calendar
+pub fn parse_date(date: &str) -> Result<Int, Box<dyn Error>> {
+    let split: Vec<&str> = date.split('-').collect();
+    if split.len() != 3 {
+        return Err(format!("dates are YYYY-MM-DD, got {}", date).into());
+    }
+    let (year, month, day) =
+        (split[0].parse::<Int>()?, split[1].parse::<Int>()?, split[2].parse::<Int>()?);
+    if !(1..=12).contains(&month) {
+        return Err(format!("no such date {}", date).into());
+    }
+    let next = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    let length = days_from_civil(next.0, next.1, 1) - days_from_civil(year, month, 1);
+    if !(1..=length).contains(&day) {
+        return Err(format!("no such date {}", date).into());
+    }
+    Ok(days_from_civil(year, month, day))
+}
+
+/// julian day for either a plain julian day number or a `YYYY-MM-DD` date at midnight
+pub fn julian_day(value: &str) -> Result<f64, Box<dyn Error>> {
+    match value.parse::<f64>() {
+        Ok(day) => Ok(day),
+        Err(_) => Ok(parse_date(value)? as f64 + UNIXJULIANDAY),
+    }
+}
+
+/// `YYYY-MM-DD` for the day a julian day falls on
+pub fn format_date(julianday: f64) -> String {
+    let days = (julianday - UNIXJULIANDAY).floor() as Int + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn days_from_civil(year: Int, month: Int, day: Int) -> Int {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
 #[inline]
 pub fn dump<Any>(_thing: Any) {}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn month_lengths() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert!(parse_date("2024-02-29").is_ok());
+        assert!(parse_date("2025-02-29").is_err());
+        assert!(parse_date("2025-02-31").is_err());
+        assert!(parse_date("2025-04-31").is_err());
+        assert!(parse_date("2025-12-31").is_ok());
+        assert!(parse_date("2025-13-01").is_err());
+    }
+
+    #[test]
+    fn date_round_trip() {
+        assert_eq!(format_date(2451544.5), "2000-01-01");
+        assert_eq!(format_date(2460676.0), "2024-12-31");
+        // every day from 1600 to 2400 formats back to itself, whatever the time of day
+        (-135140..157800).for_each(|days| {
+            let julianday = days as f64 + UNIXJULIANDAY;
+            let date = format_date(julianday + 0.75);
+            assert_eq!(parse_date(&date).unwrap(), days, "{}", date);
+            assert_eq!(julian_day(&date).unwrap(), julianday, "{}", date);
+        });
+    }
+}